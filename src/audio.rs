@@ -3,6 +3,7 @@ use fastrand::Rng;
 
 use crate::deck::TopTileRotated;
 use crate::objective::Victory;
+use crate::save::Save;
 use crate::state::GameState;
 use crate::world::PlaceTile;
 
@@ -15,7 +16,8 @@ impl Plugin for AudioPlugin {
             .add_system(on_rotate)
             .add_system(on_victory)
             .add_system(on_place)
-            .add_system(on_menu.in_schedule(OnEnter(GameState::MainMenu)));
+            .add_system(on_menu.in_schedule(OnEnter(GameState::MainMenu)))
+            .add_system(toggle_music);
     }
 }
 
@@ -25,6 +27,7 @@ struct AudioContainer {
     pub fanfare: Vec<Handle<AudioSource>>,
     pub rotate: Vec<Handle<AudioSource>>,
     pub place: Vec<Handle<AudioSource>>,
+    pub playing: Handle<AudioSink>,
 }
 
 fn load_audio(mut ac: ResMut<AudioContainer>, asset_server: Res<AssetServer>) {
@@ -39,8 +42,13 @@ fn load_audio(mut ac: ResMut<AudioContainer>, asset_server: Res<AssetServer>) {
     ac.place.push(asset_server.load("audio/thud3.ogg"));
 }
 
-fn on_rotate(event: EventReader<TopTileRotated>, ac: Res<AudioContainer>, audio: Res<Audio>) {
-    if !event.is_empty() {
+fn on_rotate(
+    event: EventReader<TopTileRotated>,
+    ac: Res<AudioContainer>,
+    audio: Res<Audio>,
+    save: Res<Save>,
+) {
+    if !event.is_empty() && save.settings.sounds {
         let rnd = Rng::new();
         audio.play_with_settings(
             ac.rotate[rnd.usize(0..ac.rotate.len())].clone(),
@@ -49,8 +57,13 @@ fn on_rotate(event: EventReader<TopTileRotated>, ac: Res<AudioContainer>, audio:
     }
 }
 
-fn on_place(event: EventReader<PlaceTile>, ac: Res<AudioContainer>, audio: Res<Audio>) {
-    if !event.is_empty() {
+fn on_place(
+    event: EventReader<PlaceTile>,
+    ac: Res<AudioContainer>,
+    audio: Res<Audio>,
+    save: Res<Save>,
+) {
+    if !event.is_empty() && save.settings.sounds {
         let rnd = Rng::new();
         audio.play_with_settings(
             ac.place[rnd.usize(0..ac.place.len())].clone(),
@@ -59,14 +72,41 @@ fn on_place(event: EventReader<PlaceTile>, ac: Res<AudioContainer>, audio: Res<A
     }
 }
 
-fn on_victory(event: EventReader<Victory>, ac: Res<AudioContainer>, audio: Res<Audio>) {
-    if !event.is_empty() {
+fn on_victory(
+    event: EventReader<Victory>,
+    ac: Res<AudioContainer>,
+    audio: Res<Audio>,
+    save: Res<Save>,
+) {
+    if !event.is_empty() && save.settings.sounds {
         let rnd = Rng::new();
         audio.play(ac.fanfare[rnd.usize(0..ac.fanfare.len())].clone());
     }
 }
 
-fn on_menu(ac: Res<AudioContainer>, audio: Res<Audio>) {
+fn on_menu(
+    mut ac: ResMut<AudioContainer>,
+    audio: Res<Audio>,
+    sinks: Res<Assets<AudioSink>>,
+    save: Res<Save>,
+) {
+    if !save.settings.music {
+        return;
+    }
     let rnd = Rng::new();
-    audio.play(ac.music[rnd.usize(0..ac.music.len())].clone());
+    let music = audio.play(ac.music[rnd.usize(0..ac.music.len())].clone());
+    ac.playing = sinks.get_handle(music);
+}
+
+/// Pause or resume the music when the setting changes
+fn toggle_music(save: Res<Save>, ac: Res<AudioContainer>, sinks: Res<Assets<AudioSink>>) {
+    if save.is_changed() {
+        if let Some(sink) = sinks.get(&ac.playing) {
+            if save.settings.music {
+                sink.play();
+            } else {
+                sink.pause();
+            }
+        }
+    }
 }
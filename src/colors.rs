@@ -41,3 +41,33 @@ pub fn blue() -> Color {
     // #6DA9E4
     Color::rgb_u8(109, 169, 228)
 }
+
+#[allow(unused)]
+pub fn forest() -> Color {
+    // #768C48
+    Color::rgb_u8(118, 140, 72)
+}
+
+#[allow(unused)]
+pub fn hills() -> Color {
+    // #D6BE78
+    Color::rgb_u8(214, 190, 120)
+}
+
+#[allow(unused)]
+pub fn water() -> Color {
+    // #78AFDC
+    Color::rgb_u8(120, 175, 220)
+}
+
+#[allow(unused)]
+pub fn red() -> Color {
+    // #E4717A
+    Color::rgb_u8(228, 113, 122)
+}
+
+#[allow(unused)]
+pub fn purple() -> Color {
+    // #A98BD6
+    Color::rgb_u8(169, 139, 214)
+}
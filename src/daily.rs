@@ -0,0 +1,34 @@
+use bevy::prelude::*;
+use trucking_core::daily;
+
+use crate::levels::{LevelAsset, LevelRegistry};
+use crate::state::{CurrentLevel, GameState};
+
+/// The current day, see `daily::day_from_unix`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn today() -> i64 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    daily::day_from_unix(now.as_secs())
+}
+
+/// The current day, see `daily::day_from_unix`
+#[cfg(target_arch = "wasm32")]
+pub fn today() -> i64 {
+    daily::day_from_unix((js_sys::Date::now() / 1000.) as u64)
+}
+
+/// Play the level of the day, it is only generated once
+pub fn play_daily(
+    state: &mut NextState<GameState>,
+    current: &mut CurrentLevel,
+    registry: &mut LevelRegistry,
+    assets: &mut Assets<LevelAsset>,
+) {
+    let day = today();
+    current.0 = registry
+        .find(assets, &daily::name(day))
+        .unwrap_or_else(|| registry.register(assets.add(LevelAsset(daily::level(day)))));
+    state.set(GameState::InGame);
+}
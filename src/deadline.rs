@@ -0,0 +1,200 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use trucking_core::level::Deadline;
+
+use crate::colors;
+use crate::deck::Deck;
+use crate::limits::Limits;
+use crate::objective::{self, CheckObjectives, Deliveries, Failed, ObjectiveTile};
+use crate::state::GameState;
+
+/// How many steps the countdown ring has
+const RING_STEPS: usize = 24;
+/// The ring turns orange when this share of the time is left
+const HURRY: f32 = 0.25;
+
+pub struct DeadlinePlugin;
+
+impl Plugin for DeadlinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RingImages>()
+            .add_startup_system(create_rings)
+            .add_systems(
+                (
+                    start_deadlines,
+                    check_deadlines.after(objective::check_victory),
+                    update_rings.after(check_deadlines),
+                )
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
+
+/// A house that gives up if it is not connected in time
+#[derive(Component)]
+pub struct HouseDeadline {
+    deadline: Deadline,
+    /// The turn and time when the house appeared
+    start: Option<(usize, f32)>,
+}
+
+impl HouseDeadline {
+    pub fn new(deadline: Deadline) -> Self {
+        Self {
+            deadline,
+            start: None,
+        }
+    }
+
+    /// The share of the time that is left, from one down to zero
+    pub fn remaining(&self, turn: usize, time: f32) -> f32 {
+        let Some((start_turn, start_time)) = self.start else {
+            return 1.;
+        };
+        let used = match self.deadline {
+            Deadline::Turns(n) => turn.saturating_sub(start_turn) as f32 / n.max(1) as f32,
+            Deadline::Seconds(s) => (time - start_time) / s.max(f32::EPSILON),
+        };
+        (1. - used).clamp(0., 1.)
+    }
+
+    /// How many turns are left, for deadlines that count turns
+    pub fn turns_left(&self, turn: usize) -> Option<usize> {
+        match (self.deadline, self.start) {
+            (Deadline::Turns(n), Some((start, _))) => Some((start + n).saturating_sub(turn)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Component)]
+struct Ring;
+
+/// Countdown rings from empty to full
+#[derive(Resource, Default)]
+struct RingImages(Vec<Handle<Image>>);
+
+/// A ring that is filled clockwise from the top, the rest is faint
+fn ring_image(filled: f32) -> Image {
+    let size = 64;
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    let half = size as f32 * 0.5;
+    for row in 0..size {
+        for col in 0..size {
+            let dx = col as f32 + 0.5 - half;
+            let dy = half - row as f32 - 0.5;
+            let radius = (dx * dx + dy * dy).sqrt() / half;
+            if !(0.8..1.0).contains(&radius) {
+                continue;
+            }
+            let angle = (dx.atan2(dy) / TAU).rem_euclid(1.);
+            let alpha = if angle < filled { 255 } else { 64 };
+            let i = ((row * size + col) * 4) as usize;
+            image.data[i..i + 4].copy_from_slice(&[255, 255, 255, alpha]);
+        }
+    }
+    image
+}
+
+fn create_rings(mut rings: ResMut<RingImages>, mut images: ResMut<Assets<Image>>) {
+    rings.0 = (0..=RING_STEPS)
+        .map(|i| images.add(ring_image(i as f32 / RING_STEPS as f32)))
+        .collect();
+}
+
+/// Start the countdown when a house with a deadline appears
+fn start_deadlines(
+    mut cmds: Commands,
+    mut query: Query<(Entity, &mut HouseDeadline), Added<HouseDeadline>>,
+    rings: Res<RingImages>,
+    deck: Res<Deck>,
+    time: Res<Time>,
+) {
+    for (entity, mut deadline) in &mut query {
+        deadline.start = Some((deck.drawn(), time.elapsed_seconds()));
+        cmds.entity(entity).with_children(|p| {
+            p.spawn((
+                Ring,
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::splat(0.9)),
+                        ..default()
+                    },
+                    texture: rings.0[RING_STEPS].clone(),
+                    transform: Transform::from_xyz(0., 0., 0.5),
+                    ..default()
+                },
+            ));
+        });
+    }
+}
+
+/// Houses that are connected in time are done, a late one gives up and the
+/// level is lost
+#[allow(clippy::too_many_arguments)]
+fn check_deadlines(
+    mut cmds: Commands,
+    mut query: Query<(Entity, &ObjectiveTile, &HouseDeadline, &mut Sprite), Without<Failed>>,
+    mut check: EventWriter<CheckObjectives>,
+    deliveries: Res<Deliveries>,
+    mut limits: ResMut<Limits>,
+    deck: Res<Deck>,
+    time: Res<Time>,
+) {
+    if limits.defeated || deliveries.complete() {
+        return;
+    }
+    for (entity, house, deadline, mut sprite) in &mut query {
+        if deliveries.connected.contains(&house.position()) {
+            cmds.entity(entity)
+                .remove::<HouseDeadline>()
+                .despawn_descendants();
+            continue;
+        }
+        // Turns are only counted once the placement has been checked for deliveries
+        let settled = deliveries.is_changed() || matches!(deadline.deadline, Deadline::Seconds(_));
+        if settled && deadline.remaining(deck.drawn(), time.elapsed_seconds()) <= 0. {
+            sprite.color = colors::dark();
+            cmds.entity(entity)
+                .remove::<HouseDeadline>()
+                .insert(Failed)
+                .despawn_descendants();
+            limits.failed = Some("A house gave up waiting");
+            check.send(CheckObjectives);
+        }
+    }
+}
+
+fn update_rings(
+    houses: Query<(&HouseDeadline, &Children)>,
+    mut rings: Query<(&mut Handle<Image>, &mut Sprite), With<Ring>>,
+    images: Res<RingImages>,
+    deck: Res<Deck>,
+    time: Res<Time>,
+) {
+    for (deadline, children) in &houses {
+        let remaining = deadline.remaining(deck.drawn(), time.elapsed_seconds());
+        for child in children.iter() {
+            if let Ok((mut image, mut sprite)) = rings.get_mut(*child) {
+                let step = (remaining * RING_STEPS as f32).ceil() as usize;
+                *image = images.0[step.min(RING_STEPS)].clone();
+                sprite.color = if remaining <= HURRY {
+                    colors::orange()
+                } else {
+                    Color::WHITE
+                };
+            }
+        }
+    }
+}
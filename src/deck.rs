@@ -1,9 +1,8 @@
-use std::collections::VecDeque;
-
 use bevy::prelude::*;
-use fastrand::Rng;
 
+use crate::limits::Limits;
 use crate::tile::Tile;
+use crate::world::PlaceTile;
 
 pub struct DeckPlugin;
 
@@ -15,85 +14,45 @@ impl Plugin for DeckPlugin {
 
 pub struct TopTileRotated(pub Tile);
 
-#[derive(Resource, Default)]
-pub struct Deck {
-    deck: Vec<(f32, Tile)>,
-    pile: VecDeque<Tile>,
-}
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct Deck(pub trucking_core::Deck);
 
 impl Deck {
-    pub fn add_tile(&mut self, tile: Tile, prob: f32) {
-        self.deck.push((prob, tile));
-    }
-
-    pub fn add_all_tiles(&mut self) {
-        self.deck.push((0.3, Tile::create("tr")));
-        self.deck.push((0.3, Tile::create("tb")));
-        self.deck.push((0.2, Tile::create("trb")));
-        self.deck.push((0.1, Tile::create("trbl")));
-        self.deck.push((0.1, Tile::create("t")));
-    }
-
-    pub fn fill_pile(&mut self, num: usize, seed: u64) {
-        let rng = Rng::with_seed(seed);
-        let tot: f32 = self.deck.iter().map(|(p, _)| p).sum();
-        self.pile.reserve(num);
-        for _ in 0..num {
-            let mut r = rng.f32() * tot;
-            for (p, t) in self.deck.iter() {
-                if r < *p {
-                    self.pile.push_back(match rng.u8(0..4) {
-                        1 => t.rotate90(),
-                        2 => t.rotate180(),
-                        3 => t.rotate270(),
-                        _ => t.clone(),
-                    });
-                } else {
-                    r -= p;
-                }
-            }
+    pub fn rotate(&mut self, event: &mut EventWriter<TopTileRotated>) {
+        if let Some(t) = self.0.rotate_top() {
+            event.send(TopTileRotated(t.clone()));
         }
     }
 
-    fn add_rnd_to_pile(&mut self) {
-        let rng = Rng::new();
-        let tot: f32 = self.deck.iter().map(|(p, _)| p).sum();
-        let mut r = rng.f32() * tot;
-        for (p, t) in self.deck.iter() {
-            if r < *p {
-                self.pile.push_back(match rng.u8(0..4) {
-                    1 => t.rotate90(),
-                    2 => t.rotate180(),
-                    3 => t.rotate270(),
-                    _ => t.clone(),
-                });
-            } else {
-                r -= p;
+    /// Turn the top tile until it looks like `tile`, only the last turn is animated
+    pub fn rotate_to(&mut self, tile: &Tile, event: &mut EventWriter<TopTileRotated>) -> bool {
+        if self.get_top() == Some(tile) {
+            return true;
+        }
+        for _ in 0..3 {
+            if self.get_top().is_some_and(|t| t.rotate90() == *tile) {
+                self.rotate(event);
+                return true;
             }
+            self.0.rotate_top();
         }
+        false
     }
 
-    pub fn add_to_pile(&mut self, tile: Tile) {
-        self.pile.push_back(tile);
-    }
-
-    pub fn get_top(&self) -> Option<&Tile> {
-        self.pile.front()
-    }
-
-    pub fn rotate(&mut self, mut event: EventWriter<TopTileRotated>) {
-        if let Some(t) = self.pile.front_mut() {
-            let t2 = t.rotate90();
-            *t = t2.clone();
-            event.send(TopTileRotated(t2));
+    pub fn skip_tile(&mut self, limits: &Limits, event: &mut EventWriter<PlaceTile>) -> bool {
+        if !limits.can_skip(self) {
+            return false;
         }
+        self.0.skip();
+        event.send(PlaceTile::dummy());
+        true
     }
 
-    pub fn next(&mut self) -> &Tile {
-        self.pile.pop_front();
-        if self.pile.is_empty() {
-            self.add_rnd_to_pile();
+    pub fn hold_tile(&mut self, limits: &Limits, event: &mut EventWriter<PlaceTile>) -> bool {
+        if limits.defeated || !self.0.hold() {
+            return false;
         }
-        self.pile.front().unwrap()
+        event.send(PlaceTile::dummy());
+        true
     }
 }
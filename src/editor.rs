@@ -0,0 +1,525 @@
+use crate::camera::cursor_to_world;
+use crate::colors;
+use crate::levels::{LevelAsset, LevelRegistry};
+use crate::objective::{building_transform, goods_color};
+use crate::state::{CurrentLevel, GameState, ReturnTo};
+use crate::tile::{Tile, TileServer};
+use crate::ui::small_text;
+use crate::world::WorldMap;
+use bevy::prelude::*;
+use trucking_core::level::Goods;
+use trucking_core::{Deck, LevelData, Terrain};
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Editor>()
+            .add_system(setup_editor.in_schedule(OnEnter(GameState::Editor)))
+            .add_systems(
+                (
+                    paint_system,
+                    key_system,
+                    camera_system,
+                    load_dropped_level,
+                    update_markers,
+                    update_text,
+                )
+                    .in_set(OnUpdate(GameState::Editor)),
+            )
+            .add_system(clear_editor.in_schedule(OnExit(GameState::Editor)));
+    }
+}
+
+const SAVE_PATH: &str = "assets/levels/editor.level";
+const SHAPES: [&str; 7] = ["t", "tr", "tb", "trb", "trbl", "tb=", "trbl="];
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Tool {
+    Cell,
+    Depot,
+    House,
+    Terrain(Terrain),
+}
+
+#[derive(Resource)]
+pub struct Editor {
+    level: LevelData,
+    tool: Tool,
+    shape: usize,
+    road: Tile,
+    goods: Option<Goods>,
+    weight: usize,
+    message: String,
+    loaded: bool,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        let mut level = LevelData {
+            name: "Custom level".to_string(),
+            fill: Some((100, 42)),
+            ..default()
+        };
+        for x in -3..=3 {
+            for y in -2..=2 {
+                level.cells.push((x, y));
+            }
+        }
+        let mut deck = Deck::default();
+        deck.add_all_tiles();
+        level.tiles = deck.weights().to_vec();
+        Self {
+            level,
+            tool: Tool::Cell,
+            shape: 0,
+            road: Tile::create(SHAPES[0]),
+            goods: None,
+            weight: 0,
+            message: String::new(),
+            loaded: false,
+        }
+    }
+}
+
+impl Editor {
+    fn remove_objective(&mut self, x: i32, y: i32) {
+        self.level.depots.retain(|(dx, dy, _)| (*dx, *dy) != (x, y));
+        self.level.houses.retain(|(hx, hy, _)| (*hx, *hy) != (x, y));
+        self.level
+            .deadlines
+            .retain(|(hx, hy, _)| (*hx, *hy) != (x, y));
+        self.level.goods.retain(|(gx, gy, _)| (*gx, *gy) != (x, y));
+    }
+
+    fn set_terrain(&mut self, x: i32, y: i32, terrain: Terrain) {
+        self.level
+            .terrain
+            .retain(|(tx, ty, _)| (*tx, *ty) != (x, y));
+        if terrain != Terrain::Grass {
+            self.level.terrain.push((x, y, terrain));
+        }
+    }
+
+    fn weight_mut(&mut self, shape: &str) -> &mut f32 {
+        let index = match self
+            .level
+            .tiles
+            .iter()
+            .position(|(_, t)| t.pattern() == shape)
+        {
+            Some(i) => i,
+            None => {
+                self.level.tiles.push((0., Tile::create(shape)));
+                self.level.tiles.len() - 1
+            }
+        };
+        &mut self.level.tiles[index].0
+    }
+
+    fn weight(&self, shape: &str) -> f32 {
+        self.level
+            .tiles
+            .iter()
+            .filter(|(_, t)| t.pattern() == shape)
+            .map(|(p, _)| p)
+            .sum()
+    }
+
+    /// Check that the level can be loaded from a level file
+    fn validate(&self) -> Result<LevelData, String> {
+        let level = LevelData::parse(&self.level.to_string()).map_err(|e| e.to_string())?;
+        // The level file runs out of symbols for too many objectives
+        if level.depots.len() < self.level.depots.len() {
+            return Err(format!(
+                "a level has at most {} distribution centers",
+                level.depots.len()
+            ));
+        }
+        if level.houses.len() < self.level.houses.len() {
+            return Err(format!("a level has at most {} houses", level.houses.len()));
+        }
+        if level.houses.is_empty() {
+            return Err("the map has no house".to_string());
+        }
+        if level.pile.is_empty() && level.tiles.iter().all(|(p, _)| *p <= 0.) {
+            return Err("the deck has no tiles".to_string());
+        }
+        Ok(level)
+    }
+
+    fn describe(&self) -> String {
+        let tool = match self.tool {
+            Tool::Cell => "Cells",
+            Tool::Depot => "Distribution center",
+            Tool::House => "House",
+            Tool::Terrain(terrain) => terrain.name(),
+        };
+        let goods = match self.goods {
+            Some(goods) => goods.to_string(),
+            None => "any".to_string(),
+        };
+        let separate = if self.level.separate { "on" } else { "off" };
+        let mut text = format!(
+            "{}\nTool: {} (1-6)\nRoads: {} (TAB, SPACE)\nGoods: {} (G)\nSeparate: {} (N)\n\nTiles (UP, DOWN, LEFT, RIGHT):\n",
+            self.level.name,
+            tool,
+            self.road.pattern(),
+            goods,
+            separate
+        );
+        for (i, shape) in SHAPES.iter().enumerate() {
+            let mark = if i == self.weight { ">" } else { " " };
+            text += &format!("{} {:<5} {:.2}\n", mark, shape, self.weight(shape));
+        }
+        let (num, seed) = self.level.fill.unwrap_or_default();
+        text += &format!("Pile: {} (-, =)\nSeed: {} ([, ])\n\n", num, seed);
+        text += "Left click: paint\nRight click: erase\nWASD, Q, E: camera\nP: test play\nCtrl+S: save\nESC: menu";
+        if !self.message.is_empty() {
+            text += &format!("\n\n{}", self.message);
+        }
+        text
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_level(path: &std::path::Path) -> Result<LevelData, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    LevelData::parse(&text).map_err(|e| e.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_level(_path: &std::path::Path) -> Result<LevelData, String> {
+    Err("Loading files is not supported in the browser".to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_level(level: &LevelData) -> Result<(), String> {
+    std::fs::write(SAVE_PATH, level.to_string()).map_err(|e| e.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_level(_level: &LevelData) -> Result<(), String> {
+    Err("Saving is not supported in the browser".to_string())
+}
+
+#[derive(Component)]
+struct EditorUI;
+
+#[derive(Component)]
+struct EditorText;
+
+#[derive(Component)]
+struct EditorMarker;
+
+fn reset_cells(cmds: &mut Commands, wm: &mut WorldMap, level: &LevelData) {
+    let old: Vec<(i32, i32)> = wm.board.cells().map(|(pos, _)| *pos).collect();
+    for (x, y) in old {
+        wm.remove_tile(x, y, cmds);
+    }
+    for (x, y) in level.cells.iter() {
+        wm.create_tile(*x, *y, cmds);
+    }
+    for (x, y, terrain) in level.terrain.iter() {
+        wm.set_terrain(*x, *y, *terrain, cmds);
+    }
+}
+
+fn setup_editor(
+    mut cmds: Commands,
+    mut editor: ResMut<Editor>,
+    mut wm: ResMut<WorldMap>,
+    mut return_to: ResMut<ReturnTo>,
+    asset_server: Res<AssetServer>,
+) {
+    return_to.0 = GameState::MainMenu;
+    if !editor.loaded {
+        editor.loaded = true;
+        if let Ok(level) = read_level(SAVE_PATH.as_ref()) {
+            editor.level = level;
+        }
+    }
+    editor.message.clear();
+    reset_cells(&mut cmds, &mut wm, &editor.level);
+    let font = asset_server.load("Bungee-Regular.ttf");
+    let mut text = small_text("", font);
+    text.style.position = UiRect::new(Val::Px(10.), Val::Auto, Val::Px(10.), Val::Auto);
+    text.style.position_type = PositionType::Absolute;
+    text.background_color = colors::light_green().into();
+    cmds.spawn((EditorUI, EditorText, text));
+}
+
+fn clear_editor(
+    mut cmds: Commands,
+    ui: Query<Entity, With<EditorUI>>,
+    markers: Query<Entity, With<EditorMarker>>,
+    mut wm: ResMut<WorldMap>,
+    mut camera: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+) {
+    for entity in &ui {
+        cmds.entity(entity).despawn_recursive();
+    }
+    for entity in &markers {
+        cmds.entity(entity).despawn_recursive();
+    }
+    reset_cells(&mut cmds, &mut wm, &LevelData::default());
+    let (mut tr, mut projection) = camera.single_mut();
+    tr.translation = Vec3::new(0., 0., 10.);
+    projection.scale = 1.;
+}
+
+fn paint_system(
+    mut cmds: Commands,
+    mut editor: ResMut<Editor>,
+    mut wm: ResMut<WorldMap>,
+    input: Res<Input<MouseButton>>,
+    windows: Query<&Window>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera>>,
+) {
+    let Some(cursor) = cursor_to_world(windows, cameras) else {
+        return;
+    };
+    let pos = cursor.round().as_ivec2();
+    let (x, y) = (pos.x, pos.y);
+    if input.pressed(MouseButton::Left) {
+        if !wm.board.contains(x, y) {
+            wm.create_tile(x, y, &mut cmds);
+            editor.level.cells.push((x, y));
+        }
+        // Depots and houses are always built on grass
+        let terrain = match editor.tool {
+            Tool::Terrain(terrain) => terrain,
+            _ => Terrain::Grass,
+        };
+        if editor.level.terrain(x, y) != terrain {
+            editor.remove_objective(x, y);
+            editor.set_terrain(x, y, terrain);
+            wm.set_terrain(x, y, terrain, &mut cmds);
+        }
+        if input.just_pressed(MouseButton::Left) && matches!(editor.tool, Tool::Depot | Tool::House)
+        {
+            editor.remove_objective(x, y);
+            let road = editor.road.clone();
+            if editor.tool == Tool::Depot {
+                editor.level.depots.push((x, y, road));
+            } else {
+                editor.level.houses.push((x, y, road));
+            }
+            if let Some(goods) = editor.goods {
+                editor.level.goods.push((x, y, goods));
+            }
+        }
+    } else if input.pressed(MouseButton::Right) && wm.remove_tile(x, y, &mut cmds) {
+        editor.level.cells.retain(|p| *p != (x, y));
+        editor.remove_objective(x, y);
+        editor.set_terrain(x, y, Terrain::Grass);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn key_system(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    mut assets: ResMut<Assets<LevelAsset>>,
+    mut registry: ResMut<LevelRegistry>,
+    mut current: ResMut<CurrentLevel>,
+    mut return_to: ResMut<ReturnTo>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    let tools = [
+        (KeyCode::Key1, Tool::Cell),
+        (KeyCode::Key2, Tool::Depot),
+        (KeyCode::Key3, Tool::House),
+        (KeyCode::Key4, Tool::Terrain(Terrain::Forest)),
+        (KeyCode::Key5, Tool::Terrain(Terrain::Hills)),
+        (KeyCode::Key6, Tool::Terrain(Terrain::Water)),
+    ];
+    for (key, tool) in tools {
+        if keys.just_pressed(key) {
+            editor.tool = tool;
+        }
+    }
+    if keys.just_pressed(KeyCode::Space) {
+        keys.reset(KeyCode::Space);
+        editor.road = editor.road.rotate90();
+    }
+    if keys.just_pressed(KeyCode::Tab) {
+        editor.shape = (editor.shape + 1) % SHAPES.len();
+        editor.road = Tile::create(SHAPES[editor.shape]);
+    }
+    if keys.just_pressed(KeyCode::G) {
+        editor.goods = match editor.goods {
+            None => Some(Goods::ALL[0]),
+            Some(goods) => Goods::ALL.into_iter().skip_while(|g| *g != goods).nth(1),
+        };
+    }
+    if keys.just_pressed(KeyCode::N) {
+        editor.level.separate = !editor.level.separate;
+    }
+    if keys.just_pressed(KeyCode::Up) {
+        editor.weight = (editor.weight + SHAPES.len() - 1) % SHAPES.len();
+    }
+    if keys.just_pressed(KeyCode::Down) {
+        editor.weight = (editor.weight + 1) % SHAPES.len();
+    }
+    for (key, change) in [(KeyCode::Left, -0.05), (KeyCode::Right, 0.05)] {
+        if keys.just_pressed(key) {
+            let shape = SHAPES[editor.weight];
+            let weight = editor.weight_mut(shape);
+            *weight = (*weight + change).max(0.);
+        }
+    }
+    let changes = [
+        (KeyCode::LBracket, 0, -1),
+        (KeyCode::RBracket, 0, 1),
+        (KeyCode::Minus, -10, 0),
+        (KeyCode::Equals, 10, 0),
+    ];
+    for (key, num_change, seed_change) in changes {
+        if keys.just_pressed(key) {
+            let (num, seed) = editor.level.fill.unwrap_or_default();
+            editor.level.fill = Some((
+                num.saturating_add_signed(num_change),
+                seed.wrapping_add_signed(seed_change),
+            ));
+        }
+    }
+    if keys.just_pressed(KeyCode::P) {
+        keys.reset(KeyCode::P);
+        match editor.validate().and_then(|mut l| {
+            l.resolve_stars()
+                .map(|_| l)
+                .map_err(|e| format!("could not find par, {}", e))
+        }) {
+            Ok(level) => {
+                current.0 = registry.register(assets.add(LevelAsset(level)));
+                return_to.0 = GameState::Editor;
+                next_state.set(GameState::InGame);
+            }
+            Err(e) => editor.message = format!("Cannot play: {}", e),
+        }
+    }
+    if ctrl && keys.just_pressed(KeyCode::S) {
+        keys.reset(KeyCode::S);
+        editor.message = match editor.validate().and_then(|l| write_level(&l)) {
+            Ok(()) => format!("Saved to {}", SAVE_PATH),
+            Err(e) => format!("Cannot save: {}", e),
+        };
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        keys.reset(KeyCode::Escape);
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+fn camera_system(
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut camera: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+) {
+    if keys.any_pressed([KeyCode::LControl, KeyCode::RControl]) {
+        return;
+    }
+    let (mut tr, mut projection) = camera.single_mut();
+    let mut dir = Vec2::ZERO;
+    if keys.pressed(KeyCode::W) {
+        dir.y += 1.;
+    }
+    if keys.pressed(KeyCode::A) {
+        dir.x -= 1.;
+    }
+    if keys.pressed(KeyCode::S) {
+        dir.y -= 1.;
+    }
+    if keys.pressed(KeyCode::D) {
+        dir.x += 1.;
+    }
+    let speed = 5. * projection.scale * time.delta_seconds();
+    tr.translation += (dir * speed).extend(0.);
+    if keys.pressed(KeyCode::Q) {
+        projection.scale = (projection.scale * (1. + time.delta_seconds())).min(4.);
+    }
+    if keys.pressed(KeyCode::E) {
+        projection.scale = (projection.scale * (1. - time.delta_seconds())).max(0.5);
+    }
+}
+
+/// Edit a level file that is dropped onto the window
+fn load_dropped_level(
+    mut cmds: Commands,
+    mut events: EventReader<FileDragAndDrop>,
+    mut editor: ResMut<Editor>,
+    mut wm: ResMut<WorldMap>,
+) {
+    for ev in events.iter() {
+        if let FileDragAndDrop::DroppedFile { path_buf, .. } = ev {
+            match read_level(path_buf) {
+                Ok(level) => {
+                    reset_cells(&mut cmds, &mut wm, &level);
+                    editor.level = level;
+                    editor.message = format!("Opened {}", path_buf.display());
+                }
+                Err(e) => editor.message = format!("Cannot open: {}", e),
+            }
+        }
+    }
+}
+
+fn update_markers(
+    mut cmds: Commands,
+    editor: Res<Editor>,
+    markers: Query<Entity, With<EditorMarker>>,
+    ts: Res<TileServer>,
+    asset_server: Res<AssetServer>,
+) {
+    if !editor.is_changed() {
+        return;
+    }
+    for entity in &markers {
+        cmds.entity(entity).despawn_recursive();
+    }
+    let objectives = [
+        (&editor.level.depots, "distribution_center.png", 0.25),
+        (&editor.level.houses, "house.png", 0.3),
+    ];
+    for (list, texture, distance) in objectives {
+        for (x, y, tile) in list.iter() {
+            let (img, rot) = ts.find_texture(tile);
+            cmds.spawn((
+                EditorMarker,
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::ONE),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(*x as f32, *y as f32, 0.5)
+                        .with_rotation(Quat::from_rotation_z(rot)),
+                    texture: img,
+                    ..default()
+                },
+            ));
+            cmds.spawn((
+                EditorMarker,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: goods_color(editor.level.goods(*x, *y)),
+                        custom_size: Some(Vec2::ONE),
+                        ..default()
+                    },
+                    transform: building_transform(*x, *y, tile, distance),
+                    texture: asset_server.load(texture),
+                    ..default()
+                },
+            ));
+        }
+    }
+}
+
+fn update_text(editor: Res<Editor>, mut query: Query<&mut Text, With<EditorText>>) {
+    if editor.is_changed() {
+        for mut text in query.iter_mut() {
+            text.sections[0].value = editor.describe();
+        }
+    }
+}
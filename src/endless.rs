@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use trucking_core::endless::{self, Spawner};
+use trucking_core::level::Deadline;
+
+use crate::deadline::HouseDeadline;
+use crate::deck::Deck;
+use crate::levels::ActiveLevel;
+use crate::limits::Limits;
+use crate::objective::{self, setup_end_tile, Deliveries, ObjectiveTile};
+use crate::state::GameState;
+use crate::world::{self, PlaceTile, WorldMap};
+
+pub struct EndlessPlugin;
+
+impl Plugin for EndlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Endless>().add_systems(
+            (
+                grow_board.after(world::place_tile),
+                update_houses.after(objective::check_victory),
+            )
+                .distributive_run_if(is_endless)
+                .in_set(OnUpdate(GameState::InGame)),
+        );
+    }
+}
+
+/// The state of an endless level, inactive for normal levels
+#[derive(Resource, Default)]
+pub struct Endless {
+    pub active: bool,
+    spawner: Spawner,
+    /// Houses connected to the depots
+    pub connected: usize,
+    /// Road tiles connected to the depots
+    pub network: usize,
+    /// The fewest turns any house still waits
+    pub patience: Option<usize>,
+}
+
+fn is_endless(endless: Res<Endless>) -> bool {
+    endless.active
+}
+
+pub fn start_endless(mut endless: ResMut<Endless>, level: ActiveLevel) {
+    if let Some(level) = level.get().filter(|l| l.endless) {
+        let seed = level.fill.map_or(0, |(_, seed)| seed);
+        *endless = Endless {
+            active: true,
+            spawner: Spawner::new(seed),
+            ..default()
+        };
+    }
+}
+
+fn grow(wm: &mut WorldMap, pos: (i32, i32), cmds: &mut Commands) {
+    for (x, y) in endless::grow(&wm.board, pos) {
+        wm.create_tile(x, y, cmds);
+    }
+}
+
+/// Keep the edge of the board away from the roads
+fn grow_board(mut cmds: Commands, mut event: EventReader<PlaceTile>, mut wm: ResMut<WorldMap>) {
+    for ev in event.iter().filter(|ev| !ev.silent) {
+        grow(&mut wm, (ev.x, ev.y), &mut cmds);
+    }
+}
+
+/// Count the connected houses and add new houses until a house gives up
+#[allow(clippy::too_many_arguments)]
+fn update_houses(
+    mut cmds: Commands,
+    mut endless: ResMut<Endless>,
+    mut wm: ResMut<WorldMap>,
+    limits: Res<Limits>,
+    mut tile_placed: EventWriter<PlaceTile>,
+    objectives: Query<(&ObjectiveTile, Option<&HouseDeadline>)>,
+    deck: Res<Deck>,
+    deliveries: Res<Deliveries>,
+    asset_server: Res<AssetServer>,
+) {
+    if !deliveries.is_changed() || limits.failed.is_some() {
+        return;
+    }
+    let turn = deck.drawn();
+    endless.connected = deliveries.connected.len();
+    endless.patience = objectives
+        .iter()
+        .filter_map(|(_, d)| d?.turns_left(turn))
+        .min();
+    let mut network = HashSet::new();
+    for (ot, _) in objectives.iter().filter(|(ot, _)| ot.is_depot()) {
+        network.extend(wm.board.reachable(ot.position()));
+    }
+    for (ot, _) in &objectives {
+        network.remove(&ot.position());
+    }
+    endless.network = network.len();
+    if let Some(house) = endless.spawner.spawn(&wm.board, turn) {
+        grow(&mut wm, (house.x, house.y), &mut cmds);
+        setup_end_tile(
+            house.x,
+            house.y,
+            house.tile,
+            None,
+            Some(Deadline::Turns(house.patience)),
+            &mut cmds,
+            &asset_server,
+            &mut tile_placed,
+        );
+    }
+}
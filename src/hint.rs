@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use trucking_core::solver::{Move, Solver};
+
+use crate::deck::{Deck, TopTileRotated};
+use crate::limits::Limits;
+use crate::objective::{self, Deliveries, ObjectiveTile};
+use crate::replay::not_replaying;
+use crate::state::GameState;
+use crate::world::{self, PlaceTile, UnplaceTile, WorldMap};
+
+/// How long the solver may search before settling for a worse hint
+const HINT_NODES: usize = 5_000;
+
+pub struct HintPlugin;
+
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hint>()
+            .add_system(
+                key_system
+                    .run_if(not_replaying)
+                    .run_if(objective::playing)
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_system(
+                clear_hint
+                    .after(world::place_tile)
+                    .after(world::unplace_tile),
+            );
+    }
+}
+
+/// The suggested move for the current top tile
+#[derive(Resource, Default)]
+pub struct Hint {
+    pub target: Option<(i32, i32)>,
+    pub skip: bool,
+    pub used: usize,
+    pub limit: Option<usize>,
+}
+
+impl Hint {
+    pub fn can_hint(&self) -> bool {
+        self.limit.is_none_or(|l| self.used < l)
+    }
+
+    pub fn remaining(&self) -> Option<usize> {
+        self.limit.map(|l| l.saturating_sub(self.used))
+    }
+}
+
+/// Search for a good move from the current board and pile, a skip is only
+/// suggested while skips are left
+pub fn show_hint(
+    hint: &mut Hint,
+    deck: &mut Deck,
+    wm: &WorldMap,
+    deliveries: &Deliveries,
+    limits: &Limits,
+    objectives: &Query<&ObjectiveTile>,
+    mut rotated: EventWriter<TopTileRotated>,
+) {
+    if hint.target.is_some() || hint.skip || !hint.can_hint() || deliveries.complete() {
+        return;
+    }
+    let depots = objectives
+        .iter()
+        .filter(|o| o.is_depot())
+        .map(|o| o.position())
+        .collect();
+    let houses = objectives
+        .iter()
+        .filter(|o| !o.is_depot())
+        .map(|o| o.position())
+        .filter(|p| !deliveries.failed.contains(p))
+        .collect();
+    let goods: Vec<_> = objectives
+        .iter()
+        .filter_map(|o| Some((o.position().0, o.position().1, o.goods()?)))
+        .collect();
+    let pile = deck.pile().iter().cloned().collect();
+    let mut solver = Solver::new(wm.board.clone(), depots, houses, pile)
+        .with_goods(&goods, deliveries.separate)
+        .with_node_limit(HINT_NODES);
+    match solver.next_move() {
+        Some(Move::Place { x, y, tile }) => {
+            hint.target = Some((x, y));
+            deck.rotate_to(&tile, &mut rotated);
+        }
+        Some(Move::Skip) if limits.can_skip(deck) => hint.skip = true,
+        Some(Move::Skip) | None => return,
+    }
+    hint.used += 1;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn key_system(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut hint: ResMut<Hint>,
+    mut deck: ResMut<Deck>,
+    wm: Res<WorldMap>,
+    deliveries: Res<Deliveries>,
+    limits: Res<Limits>,
+    objectives: Query<&ObjectiveTile>,
+    rotated: EventWriter<TopTileRotated>,
+) {
+    if keys.just_pressed(KeyCode::H) {
+        keys.reset(KeyCode::H);
+        show_hint(
+            &mut hint,
+            &mut deck,
+            &wm,
+            &deliveries,
+            &limits,
+            &objectives,
+            rotated,
+        );
+    }
+}
+
+/// The hint is only for the tile that was on top when it was asked for
+fn clear_hint(
+    mut hint: ResMut<Hint>,
+    mut placed: EventReader<PlaceTile>,
+    mut removed: EventReader<UnplaceTile>,
+) {
+    if placed.is_empty() && removed.is_empty() {
+        return;
+    }
+    placed.clear();
+    removed.clear();
+    if hint.target.is_some() || hint.skip {
+        hint.target = None;
+        hint.skip = false;
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+
+use crate::deck::Deck;
+use crate::objective::{self, Deliveries};
+use crate::replay::{not_replaying, ActionTaken};
+use crate::state::GameState;
+use crate::tile::Tile;
+use crate::world::{PlaceTile, UnplaceTile};
+use trucking_core::replay::Action;
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<History>().add_system(
+            key_system
+                .run_if(not_replaying)
+                .run_if(objective::playing)
+                .in_set(OnUpdate(GameState::InGame)),
+        );
+    }
+}
+
+/// A placement by the player and the tile it drew from the deck. Only that
+/// tile goes back on the pile when the placement is undone, so skips and holds
+/// made since are kept.
+pub struct Turn {
+    x: i32,
+    y: i32,
+    tile: Tile,
+    /// Whether a tile had been held before the placement
+    hold_used: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct History {
+    undo: Vec<Turn>,
+    redo: Vec<Turn>,
+    pub undos: usize,
+    pub limit: Option<usize>,
+}
+
+impl History {
+    pub fn record(&mut self, x: i32, y: i32, tile: Tile, hold_used: bool) {
+        self.undo.push(Turn {
+            x,
+            y,
+            tile,
+            hold_used,
+        });
+        self.redo.clear();
+    }
+
+    /// The number of placements that have not been undone
+    pub fn placed(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() && self.limit.is_none_or(|l| self.undos < l)
+    }
+
+    pub fn remaining(&self) -> Option<usize> {
+        self.limit.map(|l| l.saturating_sub(self.undos))
+    }
+}
+
+pub fn undo(
+    history: &mut History,
+    deck: &mut Deck,
+    deliveries: &Deliveries,
+    unplace: &mut EventWriter<UnplaceTile>,
+) -> bool {
+    if !history.can_undo() || deliveries.complete() {
+        return false;
+    }
+    let Some(turn) = history.undo.pop() else {
+        return false;
+    };
+    deck.undraw(turn.tile.clone(), turn.hold_used);
+    unplace.send(UnplaceTile {
+        x: turn.x,
+        y: turn.y,
+    });
+    history.undos += 1;
+    history.redo.push(turn);
+    true
+}
+
+/// Place the undone tile again, unless it has been skipped or held since
+pub fn redo(history: &mut History, deck: &mut Deck, place: &mut EventWriter<PlaceTile>) -> bool {
+    let on_top = |turn: &Turn| {
+        deck.get_top().is_some_and(|t| {
+            [t.clone(), t.rotate90(), t.rotate180(), t.rotate270()].contains(&turn.tile)
+        })
+    };
+    if !history.redo.last().is_some_and(on_top) {
+        return false;
+    }
+    let Some(turn) = history.redo.pop() else {
+        return false;
+    };
+    deck.place();
+    place.send(PlaceTile::new(turn.x, turn.y, turn.tile.clone()));
+    history.undo.push(turn);
+    true
+}
+
+fn key_system(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut history: ResMut<History>,
+    mut deck: ResMut<Deck>,
+    deliveries: Res<Deliveries>,
+    mut place: EventWriter<PlaceTile>,
+    mut unplace: EventWriter<UnplaceTile>,
+    mut actions: EventWriter<ActionTaken>,
+) {
+    if !keys.any_pressed([KeyCode::LControl, KeyCode::RControl]) {
+        return;
+    }
+    if keys.just_pressed(KeyCode::Z) {
+        keys.reset(KeyCode::Z);
+        if undo(&mut history, &mut deck, &deliveries, &mut unplace) {
+            actions.send(ActionTaken(Action::Undo));
+        }
+    }
+    if keys.just_pressed(KeyCode::Y) {
+        keys.reset(KeyCode::Y);
+        if redo(&mut history, &mut deck, &mut place) {
+            actions.send(ActionTaken(Action::Redo));
+        }
+    }
+}
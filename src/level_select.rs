@@ -0,0 +1,266 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::ImageSampler;
+use trucking_core::{Border, LevelData, Side, Terrain, Tile};
+
+use crate::colors;
+use crate::levels::{LevelAsset, LevelRegistry};
+use crate::objective::goods_color;
+use crate::save::Save;
+use crate::state::{CurrentLevel, GameState, ReturnTo};
+use crate::ui::{button, button_image, button_text, container_full, image, small_text, title_text};
+
+pub struct LevelSelectPlugin;
+
+impl Plugin for LevelSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup_level_select.in_schedule(OnEnter(GameState::LevelSelect)))
+            .add_system(fill_grid.in_set(OnUpdate(GameState::LevelSelect)))
+            .add_system(button_level.in_set(OnUpdate(GameState::LevelSelect)))
+            .add_system(button_back.in_set(OnUpdate(GameState::LevelSelect)))
+            .add_system(key_system.in_set(OnUpdate(GameState::LevelSelect)))
+            .add_system(exit_level_select.in_schedule(OnExit(GameState::LevelSelect)));
+    }
+}
+
+#[derive(Component)]
+struct LevelSelect;
+
+#[derive(Component)]
+struct LevelGrid;
+
+#[derive(Component)]
+struct LevelButton {
+    index: usize,
+    locked: bool,
+}
+
+#[derive(Component)]
+struct BackButton;
+
+/// Pixels per cell in the thumbnails
+const CELL: usize = 3;
+
+/// A small picture of the cells, terrain, depots, and houses of a level
+fn thumbnail(level: &LevelData) -> Image {
+    let min_x = level.cells.iter().map(|(x, _)| *x).min().unwrap_or(0);
+    let max_x = level.cells.iter().map(|(x, _)| *x).max().unwrap_or(0);
+    let min_y = level.cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+    let max_y = level.cells.iter().map(|(_, y)| *y).max().unwrap_or(0);
+    let width = (max_x - min_x + 1) as usize;
+    let height = (max_y - min_y + 1) as usize;
+    // The image is square and the board is centered in it
+    let size = width.max(height) * CELL;
+    let offset_x = (size - width * CELL) / 2;
+    let offset_y = (size - height * CELL) / 2;
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: size as u32,
+            height: size as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image.sampler_descriptor = ImageSampler::nearest();
+    let mut paint = |x: i32, y: i32, px: usize, py: usize, color: Color| {
+        let col = offset_x + (x - min_x) as usize * CELL + px;
+        let row = offset_y + (max_y - y) as usize * CELL + py;
+        let i = (row * size + col) * 4;
+        image.data[i..i + 4].copy_from_slice(&color.as_rgba_f32().map(|c| (c * 255.) as u8));
+    };
+    for (x, y) in level.cells.iter() {
+        let color = match level.terrain(*x, *y) {
+            Terrain::Grass => colors::dark_green(),
+            Terrain::Forest => colors::forest(),
+            Terrain::Hills => colors::hills(),
+            Terrain::Water => colors::water(),
+        };
+        for px in 0..CELL {
+            for py in 0..CELL {
+                paint(*x, *y, px, py, color);
+            }
+        }
+    }
+    let objectives = [
+        (&level.depots, colors::dark()),
+        (&level.houses, colors::blue()),
+    ];
+    for (list, color) in objectives {
+        for (x, y, tile) in list.iter() {
+            let color = match level.goods(*x, *y) {
+                Some(goods) => goods_color(Some(goods)),
+                None => color,
+            };
+            paint(*x, *y, 1, 1, color);
+            for (px, py) in roads(tile) {
+                paint(*x, *y, px, py, color);
+            }
+        }
+    }
+    image
+}
+
+/// The pixels in a cell that show the roads of a tile
+fn roads(tile: &Tile) -> impl Iterator<Item = (usize, usize)> + '_ {
+    Side::ALL
+        .into_iter()
+        .filter(|s| tile.border(*s) == Border::Road)
+        .map(|s| match s {
+            Side::Top => (1, 0),
+            Side::Right => (2, 1),
+            Side::Bottom => (1, 2),
+            Side::Left => (0, 1),
+        })
+}
+
+fn setup_level_select(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("Bungee-Regular.ttf");
+    commands
+        .spawn((LevelSelect, container_full()))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::all(Val::Percent(95.)),
+                        align_items: AlignItems::Center,
+                        align_content: AlignContent::Center,
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        gap: Size::all(Val::Px(20.)),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(colors::light_green()),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(title_text("Levels", font.clone()));
+                    parent.spawn((
+                        LevelGrid,
+                        NodeBundle {
+                            style: Style {
+                                size: Size::width(Val::Percent(100.)),
+                                flex_wrap: FlexWrap::Wrap,
+                                justify_content: JustifyContent::Center,
+                                gap: Size::all(Val::Px(10.)),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ));
+                    parent.spawn((BackButton, button())).with_children(|p| {
+                        p.spawn(button_text("Back (ESC)", font));
+                    });
+                });
+        });
+}
+
+/// Add the levels once the whole campaign has been loaded
+#[allow(clippy::too_many_arguments)]
+fn fill_grid(
+    mut commands: Commands,
+    grid: Query<Entity, (With<LevelGrid>, Without<Children>)>,
+    registry: Res<LevelRegistry>,
+    levels: Res<Assets<LevelAsset>>,
+    save: Res<Save>,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Ok(entity) = grid.get_single() else {
+        return;
+    };
+    let campaign: Option<Vec<&LevelData>> = registry
+        .campaign()
+        .iter()
+        .map(|h| levels.get(h).map(|l| &l.0))
+        .collect();
+    let Some(campaign) = campaign.filter(|c| !c.is_empty()) else {
+        return;
+    };
+    let font = asset_server.load("Bungee-Regular.ttf");
+    let star = asset_server.load("star.png");
+    commands.entity(entity).with_children(|parent| {
+        for (index, level) in campaign.iter().enumerate() {
+            let record = save.levels.get(&level.name);
+            let locked =
+                index > 0 && record.is_none() && !save.completed(&campaign[index - 1].name);
+            let name = if locked {
+                "Locked".to_string()
+            } else {
+                format!("{}. {}", index + 1, level.name)
+            };
+            let mut thumbnail = image(images.add(thumbnail(level)));
+            thumbnail.style.size = Size::all(Val::Px(150.));
+            if locked {
+                thumbnail.background_color = colors::dark().into();
+            }
+            let mut button = button_image();
+            button.style.size = Size::new(Val::Px(250.), Val::Px(250.));
+            parent
+                .spawn((LevelButton { index, locked }, button))
+                .with_children(|p| {
+                    p.spawn(small_text(&name, font.clone()));
+                    p.spawn(NodeBundle {
+                        style: Style {
+                            gap: Size::all(Val::Px(5.)),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|p| {
+                        let stars = record.map_or(0, |r| r.stars);
+                        for i in 0..3 {
+                            let mut img = image(star.clone());
+                            img.style.size = Size::all(Val::Px(30.));
+                            img.style.border = UiRect::default();
+                            img.background_color = if i < stars {
+                                colors::orange().into()
+                            } else {
+                                colors::dark().into()
+                            };
+                            p.spawn(img);
+                        }
+                    });
+                    p.spawn(thumbnail);
+                });
+        }
+    });
+}
+
+fn button_level(
+    interaction_query: Query<(&Interaction, &LevelButton), Changed<Interaction>>,
+    mut current: ResMut<CurrentLevel>,
+    mut return_to: ResMut<ReturnTo>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction == Interaction::Clicked && !button.locked {
+            current.0 = button.index;
+            return_to.0 = GameState::LevelSelect;
+            state.set(GameState::InGame);
+        }
+    }
+}
+
+fn button_back(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<BackButton>)>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if let Ok(Interaction::Clicked) = interaction_query.get_single() {
+        state.set(GameState::MainMenu);
+    }
+}
+
+fn key_system(mut keys: ResMut<Input<KeyCode>>, mut state: ResMut<NextState<GameState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        keys.reset(KeyCode::Escape);
+        state.set(GameState::MainMenu);
+    }
+}
+
+fn exit_level_select(mut commands: Commands, query: Query<Entity, With<LevelSelect>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,92 +0,0 @@
-use crate::deck::Deck;
-use crate::objective::{setup_end_tile, setup_start_tile};
-use crate::state::GameState;
-use crate::tile::Tile;
-use crate::world::{PlaceTile, WorldMap};
-use bevy::prelude::*;
-
-pub struct LevelPlugin;
-
-impl Plugin for LevelPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_system(setup_deck.in_schedule(OnEnter(GameState::Level6)))
-            .add_systems(
-                (
-                    setup_board,
-                    apply_system_buffers,
-                    WorldMap::apply_borders,
-                    place_tiles,
-                )
-                    .chain()
-                    .in_schedule(OnEnter(GameState::Level6)),
-            );
-    }
-}
-
-fn setup_board(mut cmds: Commands, mut wm: ResMut<WorldMap>) {
-    let pattern = [
-        [0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 0],
-        [0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0],
-        [0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0],
-        [0, 1, 1, 1, 1, 0, 1, 1, 1, 1, 0],
-        [1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1],
-        [1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1],
-        [1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1],
-        [0, 1, 1, 1, 1, 0, 1, 1, 1, 1, 0],
-        [0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0],
-        [0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0],
-        [0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 0],
-    ];
-    pattern.iter().enumerate().for_each(|(i, row)| {
-        row.iter().enumerate().for_each(|(j, v)| {
-            if *v != 0 {
-                wm.create_tile(i as i32, j as i32, &mut cmds);
-            }
-        })
-    });
-}
-
-fn place_tiles(
-    mut cmds: Commands,
-    asset_server: Res<AssetServer>,
-    mut tile_placed: EventWriter<PlaceTile>,
-) {
-    setup_start_tile(
-        5,
-        0,
-        Tile::create("trl"),
-        &mut cmds,
-        &asset_server,
-        &mut tile_placed,
-    );
-    setup_end_tile(
-        0,
-        5,
-        Tile::create("trb"),
-        &mut cmds,
-        &asset_server,
-        &mut tile_placed,
-    );
-    setup_end_tile(
-        10,
-        5,
-        Tile::create("tlb"),
-        &mut cmds,
-        &asset_server,
-        &mut tile_placed,
-    );
-    setup_end_tile(
-        5,
-        10,
-        Tile::create("rbl"),
-        &mut cmds,
-        &asset_server,
-        &mut tile_placed,
-    );
-}
-
-fn setup_deck(mut deck: ResMut<Deck>) {
-    deck.add_all_tiles();
-    deck.add_tile(Tile::create("tr"), 0.1);
-    deck.fill_pile(100, 45);
-}
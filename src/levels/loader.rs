@@ -0,0 +1,285 @@
+use crate::camera::move_camera_to;
+use crate::deck::Deck;
+use crate::hint::Hint;
+use crate::history::History;
+use crate::limits::Limits;
+use crate::objective::{setup_end_tile, setup_start_tile, Deliveries};
+use crate::state::{CurrentLevel, GameState};
+use crate::ui::big_button_text;
+use crate::world::{PlaceTile, WorldMap};
+use bevy::asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use std::path::Path;
+use trucking_core::{LevelData, SaveData};
+
+use super::ui::GameUI;
+
+#[derive(TypeUuid)]
+#[uuid = "5f0b8a3e-9c61-4d2a-b7e4-1a6c3f82d915"]
+pub struct LevelAsset(pub LevelData);
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let mut level = LevelData::parse(std::str::from_utf8(bytes)?)?;
+            if let Err(e) = level.resolve_stars() {
+                // A level without stars can still be played
+                warn!("No stars for {}, {}", level.name, e);
+                level.stars = None;
+            }
+            load_context.set_default_asset(LoadedAsset::new(LevelAsset(level)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level"]
+    }
+}
+
+/// An ordered list of level files, one file name per line
+#[derive(TypeUuid)]
+#[uuid = "b31d7c40-2e8a-4f6b-9d15-7ac2e04f6b38"]
+pub struct LevelList(pub Vec<Handle<LevelAsset>>);
+
+#[derive(Default)]
+pub struct LevelListLoader;
+
+impl AssetLoader for LevelListLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let dir = load_context.path().parent().unwrap_or(Path::new(""));
+            let paths: Vec<AssetPath<'static>> = std::str::from_utf8(bytes)?
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(|l| AssetPath::new(dir.join(l), None))
+                .collect();
+            let handles = paths
+                .iter()
+                .map(|p| load_context.get_handle(p.clone()))
+                .collect();
+            load_context
+                .set_default_asset(LoadedAsset::new(LevelList(handles)).with_dependencies(paths));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["list"]
+    }
+}
+
+/// All playable levels: first the campaign, then any levels added at runtime
+#[derive(Resource, Default)]
+pub struct LevelRegistry {
+    campaign: Handle<LevelList>,
+    campaign_len: usize,
+    levels: Vec<Handle<LevelAsset>>,
+}
+
+impl LevelRegistry {
+    pub fn get(&self, index: usize) -> Option<&Handle<LevelAsset>> {
+        self.levels.get(index)
+    }
+
+    /// Add a level and return its index
+    pub fn register(&mut self, level: Handle<LevelAsset>) -> usize {
+        self.levels.push(level);
+        self.levels.len() - 1
+    }
+
+    /// Find a loaded level by name
+    pub fn find(&self, levels: &Assets<LevelAsset>, name: &str) -> Option<usize> {
+        self.levels
+            .iter()
+            .position(|h| levels.get(h).is_some_and(|l| l.0.name == name))
+    }
+
+    pub fn campaign(&self) -> &[Handle<LevelAsset>] {
+        &self.levels[..self.campaign_len]
+    }
+
+    pub fn in_campaign(&self, index: usize) -> bool {
+        index < self.campaign_len
+    }
+
+    /// The first campaign level that has not been completed
+    pub fn first_unfinished(&self, levels: &Assets<LevelAsset>, save: &SaveData) -> usize {
+        self.campaign()
+            .iter()
+            .position(|h| levels.get(h).is_none_or(|l| !save.completed(&l.0.name)))
+            .unwrap_or(0)
+    }
+
+    /// The level after `index` in the campaign, levels outside the campaign have no next level
+    pub fn next(&self, index: usize) -> Option<usize> {
+        (index + 1 < self.campaign_len).then_some(index + 1)
+    }
+}
+
+pub fn load_campaign(asset_server: Res<AssetServer>, mut registry: ResMut<LevelRegistry>) {
+    registry.campaign = asset_server.load("levels/campaign.list");
+}
+
+pub fn update_campaign(
+    mut events: EventReader<AssetEvent<LevelList>>,
+    lists: Res<Assets<LevelList>>,
+    mut registry: ResMut<LevelRegistry>,
+) {
+    for ev in events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = ev {
+            if *handle == registry.campaign {
+                let list = lists.get(handle).expect("Could not find level list");
+                let len = registry.campaign_len;
+                registry.levels.splice(..len, list.0.iter().cloned());
+                registry.campaign_len = list.0.len();
+            }
+        }
+    }
+}
+
+/// Play a level file that is dropped onto the window
+pub fn load_dropped_level(
+    state: Res<State<GameState>>,
+    mut events: EventReader<FileDragAndDrop>,
+    asset_server: Res<AssetServer>,
+    mut registry: ResMut<LevelRegistry>,
+    mut current: ResMut<CurrentLevel>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if state.0 == GameState::Editor {
+        return;
+    }
+    for ev in events.iter() {
+        if let FileDragAndDrop::DroppedFile { path_buf, .. } = ev {
+            if path_buf.extension().is_some_and(|e| e == "level") {
+                current.0 = registry.register(asset_server.load(path_buf.clone()));
+                next_state.set(GameState::InGame);
+            }
+        }
+    }
+}
+
+#[derive(SystemParam)]
+pub struct ActiveLevel<'w> {
+    current: Res<'w, CurrentLevel>,
+    registry: Res<'w, LevelRegistry>,
+    levels: Res<'w, Assets<LevelAsset>>,
+}
+
+impl ActiveLevel<'_> {
+    pub fn get(&self) -> Option<&LevelData> {
+        let handle = self.registry.get(self.current.0)?;
+        self.levels.get(handle).map(|l| &l.0)
+    }
+
+    pub fn in_campaign(&self) -> bool {
+        self.registry.in_campaign(self.current.0)
+    }
+}
+
+pub fn setup_board(mut cmds: Commands, mut wm: ResMut<WorldMap>, level: ActiveLevel) {
+    if let Some(level) = level.get() {
+        for (x, y) in level.cells.iter() {
+            wm.create_tile(*x, *y, &mut cmds);
+        }
+        for (x, y, terrain) in level.terrain.iter() {
+            wm.set_terrain(*x, *y, *terrain, &mut cmds);
+        }
+        if !level.endless {
+            wm.board.apply_borders();
+        }
+    }
+}
+
+pub fn place_tiles(
+    mut cmds: Commands,
+    asset_server: Res<AssetServer>,
+    mut tile_placed: EventWriter<PlaceTile>,
+    level: ActiveLevel,
+) {
+    if let Some(level) = level.get() {
+        for (x, y, tile) in level.depots.iter() {
+            setup_start_tile(
+                *x,
+                *y,
+                tile.clone(),
+                level.goods(*x, *y),
+                &mut cmds,
+                &asset_server,
+                &mut tile_placed,
+            );
+        }
+        for (x, y, tile) in level.houses.iter() {
+            setup_end_tile(
+                *x,
+                *y,
+                tile.clone(),
+                level.goods(*x, *y),
+                level.deadline(*x, *y),
+                &mut cmds,
+                &asset_server,
+                &mut tile_placed,
+            );
+        }
+        if let Some(text) = &level.text {
+            let font = asset_server.load("Bungee-Regular.ttf");
+            let mut text = big_button_text(text, font);
+            text.style.position = UiRect::all(Val::Px(10.));
+            text.style.position_type = PositionType::Absolute;
+            text.text.alignment = TextAlignment::Center;
+            cmds.spawn((GameUI, text));
+        }
+    }
+}
+
+pub fn setup_deck(
+    mut deck: ResMut<Deck>,
+    mut history: ResMut<History>,
+    mut hint: ResMut<Hint>,
+    mut limits: ResMut<Limits>,
+    mut deliveries: ResMut<Deliveries>,
+    level: ActiveLevel,
+) {
+    if let Some(level) = level.get() {
+        deck.0 = level.deck();
+        history.limit = level.undo_limit;
+        hint.limit = level.hint_limit;
+        limits.skips = level.skip_limit;
+        limits.moves = level.move_limit;
+        deliveries.separate = level.separate;
+        deliveries.throughput = level.throughput;
+    }
+}
+
+pub fn move_camera(query: Query<&mut Transform, With<Camera>>, level: ActiveLevel) {
+    if let Some((x, y)) = level.get().and_then(|l| l.camera) {
+        move_camera_to(query, Vec2::new(x, y));
+    }
+}
+
+/// Enter the level again if the level file had not been loaded yet
+pub fn retry_level(
+    wm: Res<WorldMap>,
+    level: ActiveLevel,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if wm.board.is_empty() && level.get().is_some() {
+        next_state.set(GameState::InGame);
+    }
+}
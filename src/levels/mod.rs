@@ -1,62 +1,105 @@
-mod level0;
-mod level1;
-mod level2;
-mod level3;
-mod level4;
-mod level5;
-mod level6;
-mod level7;
-mod level_test;
+mod loader;
 mod ui;
+
 use crate::camera::move_camera_to;
 use crate::deck::Deck;
-use crate::objective::ObjectiveTile;
-use crate::state::GameState;
-use crate::tile::Tile;
+use crate::endless::{self, Endless};
+use crate::hint::Hint;
+use crate::history::History;
+use crate::limits::Limits;
+use crate::objective::{self, Deliveries, ObjectiveTile};
+use crate::replay;
+use crate::score::Score;
+use crate::state::{CurrentLevel, GameState, ReturnTo};
+use crate::tile::Cell;
+use crate::traffic::{self, Traffic, TrafficTruck};
+use crate::trucks::{self, Truck};
 use crate::world::WorldMap;
 use bevy::prelude::*;
+pub use loader::{ActiveLevel, LevelAsset, LevelRegistry};
+pub use ui::GameUI;
 
 pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_plugin(level0::LevelPlugin)
-            .add_plugin(level1::LevelPlugin)
-            .add_plugin(level2::LevelPlugin)
-            .add_plugin(level3::LevelPlugin)
-            .add_plugin(level4::LevelPlugin)
-            .add_plugin(level5::LevelPlugin)
-            .add_plugin(level6::LevelPlugin)
-            .add_plugin(level7::LevelPlugin)
-            .add_plugin(level_test::LevelPlugin);
-
-        for d in GameState::variants() {
-            if d != GameState::MainMenu {
-                app.add_system(clear_system.in_schedule(OnExit(d)))
-                    .add_system(ui::setup_gui.in_schedule(OnEnter(d)))
-                    .add_systems(
-                        (
-                            ui::key_system,
-                            ui::button_menu,
-                            ui::button_next,
-                            ui::button_restart,
-                            ui::button_rotate,
-                            ui::update_tile,
-                            ui::on_rotate,
-                            ui::on_victory,
-                        )
-                            .in_set(OnUpdate(d)),
-                    );
-            }
-        }
+        app.add_asset::<loader::LevelAsset>()
+            .init_asset_loader::<loader::LevelLoader>()
+            .add_asset::<loader::LevelList>()
+            .init_asset_loader::<loader::LevelListLoader>()
+            .init_resource::<LevelRegistry>()
+            .init_resource::<CurrentLevel>()
+            .init_resource::<ReturnTo>()
+            .add_startup_system(loader::load_campaign)
+            .add_system(loader::update_campaign)
+            .add_system(loader::load_dropped_level)
+            .add_system(clear_system.in_schedule(OnExit(GameState::InGame)))
+            .add_system(ui::setup_gui.in_schedule(OnEnter(GameState::InGame)))
+            .add_systems(
+                (
+                    loader::setup_board,
+                    loader::place_tiles,
+                    loader::setup_deck,
+                    endless::start_endless,
+                    traffic::start_traffic,
+                    replay::start_session,
+                    loader::move_camera,
+                )
+                    .chain()
+                    .in_schedule(OnEnter(GameState::InGame)),
+            )
+            .add_systems(
+                (
+                    loader::retry_level,
+                    ui::key_system,
+                    ui::deck_key_system
+                        .run_if(replay::not_replaying)
+                        .run_if(objective::playing),
+                    ui::button_menu,
+                    ui::button_next,
+                    ui::button_restart,
+                    ui::button_rotate
+                        .run_if(replay::not_replaying)
+                        .run_if(objective::playing),
+                    ui::button_undo
+                        .run_if(replay::not_replaying)
+                        .run_if(objective::playing),
+                    ui::button_hint
+                        .run_if(replay::not_replaying)
+                        .run_if(objective::playing),
+                    ui::update_tile,
+                    ui::update_deliveries,
+                    ui::update_undo,
+                    ui::update_hint,
+                    ui::on_rotate,
+                    ui::on_victory.after(trucks::drive_trucks),
+                )
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_systems(
+                (
+                    ui::button_hold
+                        .run_if(replay::not_replaying)
+                        .run_if(objective::playing),
+                    ui::button_watch,
+                    ui::update_hold,
+                    ui::update_preview,
+                    ui::update_limits,
+                    ui::on_defeat,
+                    ui::clear_defeat,
+                )
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
     }
 }
 
 fn clear_system(
     mut commands: Commands,
-    tiles: Query<Entity, With<Tile>>,
+    tiles: Query<Entity, With<Cell>>,
     ui: Query<Entity, With<ui::GameUI>>,
     obj: Query<Entity, With<ObjectiveTile>>,
+    trucks: Query<Entity, With<Truck>>,
+    traffic: Query<Entity, With<TrafficTruck>>,
     camera: Query<&mut Transform, With<Camera>>,
 ) {
     for entity in &tiles {
@@ -68,7 +111,17 @@ fn clear_system(
     for entity in &obj {
         commands.entity(entity).despawn_recursive();
     }
+    for entity in trucks.iter().chain(&traffic) {
+        commands.entity(entity).despawn_recursive();
+    }
     move_camera_to(camera, Vec2::ZERO);
     commands.insert_resource(WorldMap::default());
     commands.insert_resource(Deck::default());
+    commands.insert_resource(Deliveries::default());
+    commands.insert_resource(History::default());
+    commands.insert_resource(Hint::default());
+    commands.insert_resource(Score::default());
+    commands.insert_resource(Limits::default());
+    commands.insert_resource(Endless::default());
+    commands.insert_resource(Traffic::default());
 }
@@ -1,16 +1,31 @@
 use crate::colors;
 use crate::deck::{Deck, TopTileRotated};
-use crate::objective::Victory;
-use crate::state::GameState;
+use crate::endless::Endless;
+use crate::hint::{self, Hint};
+use crate::history::{self, History};
+use crate::limits::{Defeat, Limits};
+use crate::objective::{Deliveries, ObjectiveTile};
+use crate::replay::{ActionTaken, Playback, Recording};
+use crate::save::Save;
+use crate::score::Score;
+use crate::state::{CurrentLevel, GameState, ReturnTo};
 use crate::tile::TileServer;
+use crate::traffic::Traffic;
+use crate::trucks::Delivered;
 use crate::ui::{
     big_button, big_button_text, button, button_image, button_text, container_border,
-    container_column_end, container_full, image,
+    container_column_end, container_full, image, small_text,
 };
-use crate::world::PlaceTile;
+use crate::world::{PlaceTile, UnplaceTile, WorldMap};
 use bevy::prelude::*;
 use bevy_easings::*;
 use std::time::Duration;
+use trucking_core::daily;
+use trucking_core::replay::Action;
+use trucking_core::save::Record;
+
+use super::loader::ActiveLevel;
+use super::LevelRegistry;
 
 #[derive(Component)]
 pub struct GameUI;
@@ -18,12 +33,31 @@ pub struct GameUI;
 pub struct RestartButton;
 #[derive(Component)]
 pub struct NextButton;
+
+#[derive(Component)]
+pub struct WatchButton;
 #[derive(Component)]
 pub struct MenuButton;
 #[derive(Component)]
 pub struct RotateButton;
 #[derive(Component)]
+pub struct UndoButton;
+#[derive(Component)]
+pub struct HintButton;
+#[derive(Component)]
 pub struct TileImage;
+#[derive(Component)]
+pub struct PreviewQueue;
+#[derive(Component)]
+pub struct HoldButton;
+#[derive(Component)]
+pub struct HoldImage;
+#[derive(Component)]
+pub struct DeliveryText;
+#[derive(Component)]
+pub struct LimitsText;
+#[derive(Component)]
+pub struct DefeatUI;
 
 pub fn setup_gui(mut commands: Commands, asset_server: Res<AssetServer>) {
     let font = asset_server.load("Bungee-Regular.ttf");
@@ -31,6 +65,14 @@ pub fn setup_gui(mut commands: Commands, asset_server: Res<AssetServer>) {
         .spawn((GameUI, container_border()))
         .with_children(|p| {
             p.spawn(container_column_end()).with_children(|p| {
+                p.spawn((LimitsText, button_text("", font.clone())));
+                p.spawn((DeliveryText, button_text("", font.clone())));
+                p.spawn((UndoButton, button())).with_children(|p| {
+                    p.spawn(button_text("Undo (^Z)", font.clone()));
+                });
+                p.spawn((HintButton, button())).with_children(|p| {
+                    p.spawn(button_text("Hint (H)", font.clone()));
+                });
                 p.spawn((RestartButton, button())).with_children(|p| {
                     p.spawn(button_text("Reset (R)", font.clone()));
                 });
@@ -39,33 +81,175 @@ pub fn setup_gui(mut commands: Commands, asset_server: Res<AssetServer>) {
                 });
             });
             p.spawn(container_column_end()).with_children(|p| {
-                p.spawn((RotateButton, button_image())).with_children(|p| {
-                    p.spawn(button_text("Rotate (SPC)", font));
-                    p.spawn((TileImage, image(asset_server.load("tile_tr.png"))));
+                p.spawn((
+                    PreviewQueue,
+                    NodeBundle {
+                        style: Style {
+                            size: Size::width(Val::Px(250.)),
+                            flex_wrap: FlexWrap::WrapReverse,
+                            justify_content: JustifyContent::End,
+                            gap: Size::all(Val::Px(10.)),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ));
+                p.spawn(NodeBundle {
+                    style: Style {
+                        gap: Size::all(Val::Px(10.)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|p| {
+                    let mut hold = button_image();
+                    hold.style.size.width = Val::Px(200.);
+                    p.spawn((HoldButton, hold)).with_children(|p| {
+                        p.spawn(button_text("Hold (C)", font.clone()));
+                        let mut img = image(asset_server.load("tile_tr.png"));
+                        img.style.size = Size::all(Val::Px(150.));
+                        img.visibility = Visibility::Hidden;
+                        p.spawn((HoldImage, img));
+                    });
+                    p.spawn((RotateButton, button_image())).with_children(|p| {
+                        p.spawn(button_text("Rotate (SPC)", font));
+                        p.spawn((TileImage, image(asset_server.load("tile_tr.png"))));
+                    });
                 });
             });
         });
 }
 
+/// Show the results once the delivery trucks have arrived
 pub fn on_victory(
     mut commands: Commands,
-    event: EventReader<Victory>,
+    mut event: EventReader<Delivered>,
+    asset_server: Res<AssetServer>,
+    score: Res<Score>,
+    recording: Res<Recording>,
+    save: Res<Save>,
+    level: ActiveLevel,
+) {
+    if event.is_empty() {
+        return;
+    }
+    event.clear();
+    let stars = level.get().map_or(0, |l| score.stars(l.star_limits()));
+    let font = asset_server.load("Bungee-Regular.ttf");
+    let star = asset_server.load("star.png");
+    let mut container = container_full();
+    container.style.position = UiRect::all(Val::Px(0.));
+    container.style.position_type = PositionType::Absolute;
+    container.style.flex_direction = FlexDirection::Column;
+    container.style.gap = Size::all(Val::Px(20.));
+    let mut breakdown = button_text(
+        &format!(
+            "Tiles placed: {}\nTiles skipped: {}\nRotations: {}\nUndos: {}\nTime: {}:{:02}\nDistance driven: {} tiles\nLast parcel: {:.1}s\nPoints: {}",
+            score.placed,
+            score.skipped,
+            score.rotations,
+            score.undos,
+            score.seconds as u32 / 60,
+            score.seconds as u32 % 60,
+            score.distance,
+            score.delivery,
+            score.points(stars),
+        ),
+        font.clone(),
+    );
+    breakdown.text.alignment = TextAlignment::Center;
+    let summary = level
+        .get()
+        .and_then(|l| daily::parse_name(&l.name))
+        .map(|day| {
+            let streak = if save.daily.day == day {
+                save.daily.length
+            } else {
+                0
+            };
+            let mut text = small_text(
+                &daily::summary(day, &Record::new(&score, stars), streak),
+                font.clone(),
+            );
+            text.text.alignment = TextAlignment::Center;
+            text
+        });
+    commands
+        .spawn((GameUI, container))
+        .with_children(|p| {
+            p.spawn(NodeBundle {
+                style: Style {
+                    gap: Size::all(Val::Px(10.)),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|p| {
+                for i in 0..3 {
+                    let mut img = image(star.clone());
+                    img.style.size = Size::all(Val::Px(100.));
+                    img.background_color = if i < stars {
+                        colors::orange().into()
+                    } else {
+                        colors::dark().into()
+                    };
+                    p.spawn(img);
+                }
+            });
+            p.spawn(breakdown);
+            if let Some(summary) = summary {
+                p.spawn(summary);
+            }
+            if recording.replay.is_some() {
+                p.spawn((WatchButton, button())).with_children(|p| {
+                    p.spawn(button_text("Watch replay", font.clone()));
+                });
+            }
+            p.spawn((NextButton, big_button())).with_children(|p| {
+                p.spawn(big_button_text("Next level (N)", font));
+            });
+        })
+        .insert(BackgroundColor(Color::NONE).ease_to(
+            colors::bright().into(),
+            EaseFunction::CubicIn,
+            EasingType::Once {
+                duration: Duration::from_millis(1000),
+            },
+        ));
+}
+
+pub fn on_defeat(
+    mut commands: Commands,
+    mut event: EventReader<Defeat>,
     asset_server: Res<AssetServer>,
+    endless: Res<Endless>,
 ) {
-    if !event.is_empty() {
+    for Defeat(reason) in event.iter() {
         let font = asset_server.load("Bungee-Regular.ttf");
         let mut container = container_full();
         container.style.position = UiRect::all(Val::Px(0.));
         container.style.position_type = PositionType::Absolute;
+        container.style.flex_direction = FlexDirection::Column;
+        container.style.gap = Size::all(Val::Px(20.));
         commands
-            .spawn((GameUI, container))
+            .spawn((GameUI, DefeatUI, container))
             .with_children(|p| {
-                p.spawn((NextButton, big_button())).with_children(|p| {
-                    p.spawn(big_button_text("Next level (N)", font));
+                p.spawn(big_button_text(reason, font.clone()));
+                if endless.active {
+                    p.spawn(button_text(
+                        &format!(
+                            "Houses connected: {}\nNetwork: {} tiles",
+                            endless.connected, endless.network
+                        ),
+                        font.clone(),
+                    ));
+                }
+                p.spawn((RestartButton, big_button())).with_children(|p| {
+                    p.spawn(big_button_text("Retry (R)", font));
                 });
             })
             .insert(BackgroundColor(Color::NONE).ease_to(
-                colors::bright().into(),
+                colors::dark_green().with_a(0.8).into(),
                 EaseFunction::CubicIn,
                 EasingType::Once {
                     duration: Duration::from_millis(1000),
@@ -74,33 +258,207 @@ pub fn on_victory(
     }
 }
 
+/// Undoing can get the level out of a dead end
+pub fn clear_defeat(
+    mut commands: Commands,
+    limits: Res<Limits>,
+    query: Query<Entity, With<DefeatUI>>,
+) {
+    if limits.is_changed() && !limits.defeated {
+        for entity in &query {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub fn update_limits(
+    deck: Res<Deck>,
+    limits: Res<Limits>,
+    mut query: Query<&mut Text, With<LimitsText>>,
+) {
+    if deck.is_changed() || limits.is_changed() {
+        if let Ok(mut text) = query.get_single_mut() {
+            let mut lines = vec![];
+            if deck.is_finite() {
+                lines.push(format!("Tiles {}", deck.pile().len()));
+            }
+            if let Some(n) = limits.remaining_skips(&deck) {
+                lines.push(format!("Skips {}", n));
+            }
+            if let Some(n) = limits.remaining_moves(&deck) {
+                lines.push(format!("Moves {}", n));
+            }
+            text.sections[0].value = lines.join("\n");
+        }
+    }
+}
+
+pub fn update_deliveries(
+    deliveries: Res<Deliveries>,
+    endless: Res<Endless>,
+    traffic: Res<Traffic>,
+    mut query: Query<&mut Text, With<DeliveryText>>,
+) {
+    if deliveries.is_changed() || endless.is_changed() || traffic.is_changed() {
+        if let Ok(mut text) = query.get_single_mut() {
+            text.sections[0].value = if endless.active {
+                let mut lines = vec![
+                    format!("Connected {}", endless.connected),
+                    format!("Network {}", endless.network),
+                ];
+                if let Some(n) = endless.patience {
+                    lines.push(format!("Hurry {}", n));
+                }
+                lines.join("\n")
+            } else if let Some(goal) = deliveries.throughput {
+                let left = (goal.seconds - traffic.seconds()).max(0.).ceil() as u32;
+                format!(
+                    "Parcels {}/{}\nTime {}:{:02}",
+                    deliveries.parcels,
+                    goal.parcels,
+                    left / 60,
+                    left % 60
+                )
+            } else {
+                format!(
+                    "Houses {}/{}",
+                    deliveries.connected.len(),
+                    deliveries.total()
+                )
+            };
+        }
+    }
+}
+
+pub fn update_undo(
+    history: Res<History>,
+    buttons: Query<&Children, With<UndoButton>>,
+    mut query: Query<&mut Text>,
+) {
+    if history.is_changed() {
+        if let Ok(children) = buttons.get_single() {
+            let mut text = query.get_mut(children[0]).expect("Could not find text");
+            text.sections[0].value = match history.remaining() {
+                Some(n) => format!("Undo (^Z) {}", n),
+                None => "Undo (^Z)".to_string(),
+            };
+        }
+    }
+}
+
+pub fn update_hint(
+    hint: Res<Hint>,
+    buttons: Query<&Children, With<HintButton>>,
+    mut query: Query<&mut Text>,
+) {
+    if hint.is_changed() {
+        if let Ok(children) = buttons.get_single() {
+            let mut text = query.get_mut(children[0]).expect("Could not find text");
+            text.sections[0].value = match (hint.skip, hint.remaining()) {
+                (true, _) => "Skip (S)".to_string(),
+                (false, Some(n)) => format!("Hint (H) {}", n),
+                (false, None) => "Hint (H)".to_string(),
+            };
+        }
+    }
+}
+
 pub fn update_tile(
     tile_placed: EventReader<PlaceTile>,
+    tile_removed: EventReader<UnplaceTile>,
+    deck: Res<Deck>,
+    mut query: Query<(&mut UiImage, &mut Transform, &mut Visibility), With<TileImage>>,
+    ts: Res<TileServer>,
+) {
+    if !tile_placed.is_empty() || !tile_removed.is_empty() {
+        if let Ok((mut img, mut tr, mut visibility)) = query.get_single_mut() {
+            // A finite pile can run out, then there is no tile to show
+            match deck.get_top() {
+                Some(tile) => {
+                    let (img2, rot) = ts.find_texture(tile);
+                    img.texture = img2;
+                    tr.rotation = Quat::from_rotation_z(-rot);
+                    *visibility = Visibility::Inherited;
+                }
+                None => *visibility = Visibility::Hidden,
+            }
+        }
+    }
+}
+
+/// Show the tiles after the top one, as many as the level allows
+#[allow(clippy::too_many_arguments)]
+pub fn update_preview(
+    mut commands: Commands,
+    tile_placed: EventReader<PlaceTile>,
+    tile_removed: EventReader<UnplaceTile>,
+    rotated: EventReader<TopTileRotated>,
     deck: Res<Deck>,
-    mut query: Query<(&mut UiImage, &mut Transform), With<TileImage>>,
+    level: ActiveLevel,
     ts: Res<TileServer>,
+    query: Query<Entity, With<PreviewQueue>>,
 ) {
-    if !tile_placed.is_empty() {
-        if let Ok((mut img, mut tr)) = query.get_single_mut() {
-            if let Some(tile) = deck.get_top() {
+    if tile_placed.is_empty() && tile_removed.is_empty() && rotated.is_empty() {
+        return;
+    }
+    let Ok(entity) = query.get_single() else {
+        return;
+    };
+    let count = level.get().map_or(0, |l| l.preview);
+    commands.entity(entity).despawn_descendants();
+    commands.entity(entity).with_children(|p| {
+        for tile in deck.pile().iter().skip(1).take(count) {
+            let (texture, rot) = ts.find_texture(tile);
+            let mut img = image(texture);
+            img.style.size = Size::all(Val::Px(55.));
+            img.style.border = UiRect::default();
+            img.transform = Transform::from_rotation(Quat::from_rotation_z(-rot));
+            p.spawn(img);
+        }
+    });
+}
+
+pub fn update_hold(
+    tile_placed: EventReader<PlaceTile>,
+    tile_removed: EventReader<UnplaceTile>,
+    deck: Res<Deck>,
+    mut query: Query<(&mut UiImage, &mut Transform, &mut Visibility), With<HoldImage>>,
+    ts: Res<TileServer>,
+) {
+    if !tile_placed.is_empty() || !tile_removed.is_empty() {
+        if let Ok((mut img, mut tr, mut visibility)) = query.get_single_mut() {
+            if let Some(tile) = deck.held() {
                 let (img2, rot) = ts.find_texture(tile);
                 img.texture = img2;
                 tr.rotation = Quat::from_rotation_z(-rot);
+                *visibility = Visibility::Inherited;
+            } else {
+                *visibility = Visibility::Hidden;
             }
         }
     }
 }
 
-fn restart_level(next_state: &mut ResMut<NextState<GameState>>, state: &Res<State<GameState>>) {
-    next_state.set(state.0);
+fn restart_level(next_state: &mut ResMut<NextState<GameState>>) {
+    next_state.set(GameState::InGame);
 }
 
-fn next_level(next_state: &mut ResMut<NextState<GameState>>, state: &Res<State<GameState>>) {
-    next_state.set(state.0.next_level());
+fn next_level(
+    next_state: &mut ResMut<NextState<GameState>>,
+    current: &mut ResMut<CurrentLevel>,
+    registry: &Res<LevelRegistry>,
+    return_to: &Res<ReturnTo>,
+) {
+    if let Some(next) = registry.next(current.0) {
+        current.0 = next;
+        next_state.set(GameState::InGame);
+    } else {
+        main_menu(next_state, return_to);
+    }
 }
 
-fn main_menu(next_state: &mut ResMut<NextState<GameState>>) {
-    next_state.set(GameState::MainMenu);
+fn main_menu(next_state: &mut ResMut<NextState<GameState>>, return_to: &Res<ReturnTo>) {
+    next_state.set(return_to.0);
 }
 
 pub fn on_rotate(
@@ -122,75 +480,155 @@ pub fn on_rotate(
     }
 }
 
-fn next_tile(mut deck: ResMut<Deck>, mut tile_placed: EventWriter<PlaceTile>) {
-    deck.next();
-    tile_placed.send(PlaceTile::dummy());
-}
-
 pub fn key_system(
     mut keys: ResMut<Input<KeyCode>>,
     mut next_state: ResMut<NextState<GameState>>,
-    state: Res<State<GameState>>,
-    mut deck: ResMut<Deck>,
-    tile_placed: EventWriter<PlaceTile>,
-    event: EventWriter<TopTileRotated>,
+    mut current: ResMut<CurrentLevel>,
+    registry: Res<LevelRegistry>,
+    return_to: Res<ReturnTo>,
 ) {
     if keys.just_pressed(KeyCode::R) {
         keys.reset(KeyCode::R);
-        restart_level(&mut next_state, &state);
+        restart_level(&mut next_state);
     }
     if keys.just_pressed(KeyCode::N) {
         keys.reset(KeyCode::N);
-        next_level(&mut next_state, &state);
+        next_level(&mut next_state, &mut current, &registry, &return_to);
     }
     if keys.just_pressed(KeyCode::Escape) {
         keys.reset(KeyCode::Escape);
-        main_menu(&mut next_state);
+        main_menu(&mut next_state, &return_to);
     }
+}
+
+pub fn deck_key_system(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut deck: ResMut<Deck>,
+    limits: Res<Limits>,
+    mut tile_placed: EventWriter<PlaceTile>,
+    mut event: EventWriter<TopTileRotated>,
+    mut actions: EventWriter<ActionTaken>,
+) {
     if keys.just_pressed(KeyCode::Space) {
         keys.reset(KeyCode::Space);
-        deck.rotate(event);
+        deck.rotate(&mut event);
     }
     if keys.just_pressed(KeyCode::S) {
         keys.reset(KeyCode::S);
-        next_tile(deck, tile_placed);
+        if deck.skip_tile(&limits, &mut tile_placed) {
+            actions.send(ActionTaken(Action::Skip));
+        }
+    } else if keys.just_pressed(KeyCode::C) {
+        keys.reset(KeyCode::C);
+        if deck.hold_tile(&limits, &mut tile_placed) {
+            actions.send(ActionTaken(Action::Hold));
+        }
     }
 }
 
 pub fn button_restart(
     interaction_query: Query<&Interaction, (Changed<Interaction>, With<RestartButton>)>,
     mut next_state: ResMut<NextState<GameState>>,
-    state: Res<State<GameState>>,
 ) {
     if let Ok(Interaction::Clicked) = interaction_query.get_single() {
-        restart_level(&mut next_state, &state);
+        restart_level(&mut next_state);
     }
 }
 pub fn button_next(
     interaction_query: Query<&Interaction, (Changed<Interaction>, With<NextButton>)>,
     mut next_state: ResMut<NextState<GameState>>,
-    state: Res<State<GameState>>,
+    mut current: ResMut<CurrentLevel>,
+    registry: Res<LevelRegistry>,
+    return_to: Res<ReturnTo>,
 ) {
     if let Ok(Interaction::Clicked) = interaction_query.get_single() {
-        next_level(&mut next_state, &state);
+        next_level(&mut next_state, &mut current, &registry, &return_to);
+    };
+}
+
+pub fn button_watch(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<WatchButton>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+    recording: Res<Recording>,
+    mut playback: ResMut<Playback>,
+) {
+    if let Ok(Interaction::Clicked) = interaction_query.get_single() {
+        if let Some(replay) = &recording.replay {
+            playback.watch(replay.clone());
+            restart_level(&mut next_state);
+        }
     };
 }
 
 pub fn button_menu(
     interaction_query: Query<&Interaction, (Changed<Interaction>, With<MenuButton>)>,
     mut next_state: ResMut<NextState<GameState>>,
+    return_to: Res<ReturnTo>,
 ) {
     if let Ok(Interaction::Clicked) = interaction_query.get_single() {
-        main_menu(&mut next_state);
+        main_menu(&mut next_state, &return_to);
     };
 }
 
 pub fn button_rotate(
     interaction_query: Query<&Interaction, (Changed<Interaction>, With<RotateButton>)>,
     mut deck: ResMut<Deck>,
+    mut event: EventWriter<TopTileRotated>,
+) {
+    if let Ok(Interaction::Clicked) = interaction_query.get_single() {
+        deck.rotate(&mut event);
+    };
+}
+
+pub fn button_hold(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<HoldButton>)>,
+    mut deck: ResMut<Deck>,
+    limits: Res<Limits>,
+    mut tile_placed: EventWriter<PlaceTile>,
+    mut actions: EventWriter<ActionTaken>,
+) {
+    if let Ok(Interaction::Clicked) = interaction_query.get_single() {
+        if deck.hold_tile(&limits, &mut tile_placed) {
+            actions.send(ActionTaken(Action::Hold));
+        }
+    };
+}
+
+pub fn button_undo(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<UndoButton>)>,
+    mut history: ResMut<History>,
+    mut deck: ResMut<Deck>,
+    deliveries: Res<Deliveries>,
+    mut event: EventWriter<UnplaceTile>,
+    mut actions: EventWriter<ActionTaken>,
+) {
+    if let Ok(Interaction::Clicked) = interaction_query.get_single() {
+        if history::undo(&mut history, &mut deck, &deliveries, &mut event) {
+            actions.send(ActionTaken(Action::Undo));
+        }
+    };
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn button_hint(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<HintButton>)>,
+    mut hint: ResMut<Hint>,
+    mut deck: ResMut<Deck>,
+    wm: Res<WorldMap>,
+    deliveries: Res<Deliveries>,
+    limits: Res<Limits>,
+    objectives: Query<&ObjectiveTile>,
     event: EventWriter<TopTileRotated>,
 ) {
     if let Ok(Interaction::Clicked) = interaction_query.get_single() {
-        deck.rotate(event);
+        hint::show_hint(
+            &mut hint,
+            &mut deck,
+            &wm,
+            &deliveries,
+            &limits,
+            &objectives,
+            event,
+        );
     };
 }
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+
+use crate::deck::Deck;
+use crate::objective::{self, CheckObjectives, Deliveries};
+use crate::world::{PlaceTile, UnplaceTile, WorldMap};
+
+pub struct LimitsPlugin;
+
+impl Plugin for LimitsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Limits>()
+            .add_event::<Defeat>()
+            .add_system(check_defeat.after(objective::check_victory));
+    }
+}
+
+/// The level is lost, with the reason why
+pub struct Defeat(pub &'static str);
+
+/// How many skips and moves the level allows, and whether they have run out
+#[derive(Resource, Default)]
+pub struct Limits {
+    pub skips: Option<usize>,
+    pub moves: Option<usize>,
+    pub defeated: bool,
+    /// A loss that cannot be taken back
+    pub failed: Option<&'static str>,
+}
+
+impl Limits {
+    pub fn can_skip(&self, deck: &Deck) -> bool {
+        !self.defeated && self.skips.is_none_or(|l| deck.skipped() < l)
+    }
+
+    pub fn remaining_skips(&self, deck: &Deck) -> Option<usize> {
+        self.skips.map(|l| l.saturating_sub(deck.skipped()))
+    }
+
+    pub fn remaining_moves(&self, deck: &Deck) -> Option<usize> {
+        self.moves.map(|l| l.saturating_sub(deck.drawn()))
+    }
+
+    /// Why the level can no longer be won, if it cannot
+    fn defeat(&self, deck: &Deck, wm: &WorldMap, deliveries: &Deliveries) -> Option<&'static str> {
+        if self.failed.is_some() {
+            return self.failed;
+        }
+        if deliveries.mixed {
+            return Some("The goods got mixed");
+        }
+        if self.remaining_moves(deck) == Some(0) {
+            return Some("Out of moves");
+        }
+        let Some(top) = deck.get_top() else {
+            return Some("Out of tiles");
+        };
+        let board = &wm.board;
+        if deck.is_finite()
+            && !deck
+                .pile()
+                .iter()
+                .chain(deck.held())
+                .any(|t| board.fits_anywhere(t))
+        {
+            return Some("No tile fits");
+        }
+        let can_hold = deck.can_hold()
+            && match deck.held() {
+                Some(held) => board.fits_anywhere(held),
+                None => deck.pile().len() > 1 || !deck.is_finite(),
+            };
+        if !board.fits_anywhere(top) && !can_hold && !self.can_skip(deck) {
+            return Some("No tile fits and no skips left");
+        }
+        None
+    }
+}
+
+/// Check the limits after every placement, skip, and undo, and when asked to
+#[allow(clippy::too_many_arguments)]
+pub fn check_defeat(
+    mut placed: EventReader<PlaceTile>,
+    mut removed: EventReader<UnplaceTile>,
+    mut check: EventReader<CheckObjectives>,
+    mut defeat: EventWriter<Defeat>,
+    mut limits: ResMut<Limits>,
+    deck: Res<Deck>,
+    deliveries: Res<Deliveries>,
+    wm: Res<WorldMap>,
+) {
+    if placed.is_empty() && removed.is_empty() && check.is_empty() {
+        return;
+    }
+    placed.clear();
+    removed.clear();
+    check.clear();
+    if wm.board.is_empty() || deliveries.complete() {
+        return;
+    }
+    let reason = limits.defeat(&deck, &wm, &deliveries);
+    if limits.defeated != reason.is_some() {
+        limits.defeated = reason.is_some();
+        if let Some(reason) = reason {
+            defeat.send(Defeat(reason));
+        }
+    }
+}
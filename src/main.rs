@@ -1,12 +1,25 @@
 mod audio;
 mod camera;
 mod colors;
+mod daily;
+mod deadline;
 mod deck;
+mod editor;
+mod endless;
+mod hint;
+mod history;
+mod level_select;
 mod levels;
+mod limits;
 mod main_menu;
 mod objective;
+mod replay;
+mod save;
+mod score;
 mod state;
 mod tile;
+mod traffic;
+mod trucks;
 mod ui;
 mod world;
 
@@ -25,15 +38,27 @@ fn main() {
             ..default()
         }))
         .add_plugin(EasingsPlugin)
+        .add_plugin(save::SavePlugin)
         .add_plugin(camera::CameraPlugin)
         .add_plugin(tile::TilePlugin)
         .add_plugin(ui::UiPlugin)
         .add_plugin(deck::DeckPlugin)
         .add_plugin(world::WorldPlugin)
         .add_plugin(objective::ObjectivePlugin)
+        .add_plugin(history::HistoryPlugin)
+        .add_plugin(hint::HintPlugin)
+        .add_plugin(score::ScorePlugin)
+        .add_plugin(trucks::TruckPlugin)
+        .add_plugin(limits::LimitsPlugin)
+        .add_plugin(deadline::DeadlinePlugin)
+        .add_plugin(endless::EndlessPlugin)
+        .add_plugin(traffic::TrafficPlugin)
+        .add_plugin(replay::ReplayPlugin)
         .add_state::<state::GameState>()
         .add_plugin(audio::AudioPlugin)
         .add_plugin(main_menu::MainMenuPlugin)
         .add_plugin(levels::LevelPlugin)
+        .add_plugin(level_select::LevelSelectPlugin)
+        .add_plugin(editor::EditorPlugin)
         .run();
 }
@@ -1,7 +1,14 @@
 use crate::colors;
-use crate::state::GameState;
-use crate::ui::{big_button, big_button_text, container_full, title_text};
+use crate::daily::{play_daily, today};
+use crate::levels::{LevelAsset, LevelRegistry};
+use crate::save::Save;
+use crate::state::{CurrentLevel, GameState, ReturnTo};
+use crate::ui::{
+    big_button, big_button_text, button, button_text, container_full, small_text, title_text,
+};
 use bevy::prelude::*;
+use trucking_core::endless;
+use trucking_core::generator::{self, Difficulty};
 
 pub struct MainMenuPlugin;
 
@@ -9,6 +16,13 @@ impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(setup_menu.in_schedule(OnEnter(GameState::MainMenu)))
             .add_system(button_system.in_set(OnUpdate(GameState::MainMenu)))
+            .add_system(button_levels.in_set(OnUpdate(GameState::MainMenu)))
+            .add_system(button_editor.in_set(OnUpdate(GameState::MainMenu)))
+            .add_system(button_random.in_set(OnUpdate(GameState::MainMenu)))
+            .add_system(button_daily.in_set(OnUpdate(GameState::MainMenu)))
+            .add_system(button_endless.in_set(OnUpdate(GameState::MainMenu)))
+            .add_system(button_settings.in_set(OnUpdate(GameState::MainMenu)))
+            .add_system(seed_system.in_set(OnUpdate(GameState::MainMenu)))
             .add_system(key_system.in_set(OnUpdate(GameState::MainMenu)))
             .add_system(exit_menu.in_schedule(OnExit(GameState::MainMenu)));
     }
@@ -20,24 +34,209 @@ struct MainMenu;
 #[derive(Component)]
 struct PlayButton;
 
+#[derive(Component)]
+struct LevelsButton;
+
+#[derive(Component)]
+struct EditorButton;
+
+#[derive(Component)]
+struct RandomButton(Difficulty);
+
+#[derive(Component)]
+struct DailyButton;
+
+#[derive(Component)]
+struct EndlessButton;
+
+#[derive(Component)]
+struct SeedText;
+
+#[derive(Component, Clone, Copy)]
+enum SettingButton {
+    Music,
+    Sounds,
+}
+
+impl SettingButton {
+    fn text(self, save: &Save) -> String {
+        let (name, value) = match self {
+            SettingButton::Music => ("Music", save.settings.music),
+            SettingButton::Sounds => ("Sounds", save.settings.sounds),
+        };
+        format!("{}: {}", name, if value { "on" } else { "off" })
+    }
+}
+
+/// The seed for the next random level
+#[derive(Resource)]
+struct Seed(u64);
+
+fn seed_text(seed: u64) -> String {
+    format!("Random level, seed {} (type to change)", seed)
+}
+
+fn daily_text(save: &Save) -> String {
+    let today = today();
+    match save.daily.current(today) {
+        _ if save.daily.completed(today) => {
+            format!("Done for today, streak {}", save.daily.length)
+        }
+        0 => "A new level every day".to_string(),
+        streak => format!("Keep your streak of {} going", streak),
+    }
+}
+
+/// Continue the campaign from the first level that has not been completed
+fn play(
+    state: &mut NextState<GameState>,
+    current: &mut CurrentLevel,
+    registry: &LevelRegistry,
+    levels: &Assets<LevelAsset>,
+    save: &Save,
+) {
+    current.0 = registry.first_unfinished(levels, save);
+    state.set(GameState::InGame);
+}
+
 fn button_system(
     interaction_query: Query<&Interaction, (Changed<Interaction>, With<PlayButton>)>,
     mut state: ResMut<NextState<GameState>>,
+    mut current: ResMut<CurrentLevel>,
+    registry: Res<LevelRegistry>,
+    levels: Res<Assets<LevelAsset>>,
+    save: Res<Save>,
 ) {
     if let Ok(Interaction::Clicked) = interaction_query.get_single() {
-        state.set(GameState::Level0);
+        play(&mut state, &mut current, &registry, &levels, &save);
     };
 }
 
-fn key_system(mut keys: ResMut<Input<KeyCode>>, mut state: ResMut<NextState<GameState>>) {
+fn button_settings(
+    interaction_query: Query<(&Interaction, &SettingButton, &Children), Changed<Interaction>>,
+    mut save: ResMut<Save>,
+    mut texts: Query<&mut Text>,
+) {
+    for (interaction, button, children) in &interaction_query {
+        if *interaction == Interaction::Clicked {
+            let mut settings = save.settings;
+            match button {
+                SettingButton::Music => settings.music = !settings.music,
+                SettingButton::Sounds => settings.sounds = !settings.sounds,
+            }
+            save.set_settings(settings);
+            if let Ok(mut text) = texts.get_mut(children[0]) {
+                text.sections[0].value = button.text(&save);
+            }
+        }
+    }
+}
+
+fn button_levels(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<LevelsButton>)>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if let Ok(Interaction::Clicked) = interaction_query.get_single() {
+        state.set(GameState::LevelSelect);
+    };
+}
+
+fn button_editor(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<EditorButton>)>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if let Ok(Interaction::Clicked) = interaction_query.get_single() {
+        state.set(GameState::Editor);
+    };
+}
+
+fn button_random(
+    interaction_query: Query<(&Interaction, &RandomButton), Changed<Interaction>>,
+    seed: Res<Seed>,
+    mut assets: ResMut<Assets<LevelAsset>>,
+    mut registry: ResMut<LevelRegistry>,
+    mut current: ResMut<CurrentLevel>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, difficulty) in &interaction_query {
+        if *interaction == Interaction::Clicked {
+            let level = generator::generate(seed.0, difficulty.0);
+            current.0 = registry.register(assets.add(LevelAsset(level)));
+            state.set(GameState::InGame);
+        }
+    }
+}
+
+fn button_daily(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<DailyButton>)>,
+    mut assets: ResMut<Assets<LevelAsset>>,
+    mut registry: ResMut<LevelRegistry>,
+    mut current: ResMut<CurrentLevel>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if let Ok(Interaction::Clicked) = interaction_query.get_single() {
+        play_daily(&mut state, &mut current, &mut registry, &mut assets);
+    };
+}
+
+fn button_endless(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<EndlessButton>)>,
+    seed: Res<Seed>,
+    mut assets: ResMut<Assets<LevelAsset>>,
+    mut registry: ResMut<LevelRegistry>,
+    mut current: ResMut<CurrentLevel>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if let Ok(Interaction::Clicked) = interaction_query.get_single() {
+        let level = endless::level(seed.0);
+        current.0 = registry.register(assets.add(LevelAsset(level)));
+        state.set(GameState::InGame);
+    };
+}
+
+fn seed_system(
+    mut chars: EventReader<ReceivedCharacter>,
+    mut seed: ResMut<Seed>,
+    mut query: Query<&mut Text, With<SeedText>>,
+) {
+    for ev in chars.iter() {
+        if let Some(digit) = ev.char.to_digit(10) {
+            seed.0 = (seed.0 * 10 + digit as u64) % 1_000_000_000;
+        } else if ev.char == '\u{8}' {
+            seed.0 /= 10;
+        }
+    }
+    if seed.is_changed() {
+        if let Ok(mut text) = query.get_single_mut() {
+            text.sections[0].value = seed_text(seed.0);
+        }
+    }
+}
+
+fn key_system(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut state: ResMut<NextState<GameState>>,
+    mut current: ResMut<CurrentLevel>,
+    registry: Res<LevelRegistry>,
+    levels: Res<Assets<LevelAsset>>,
+    save: Res<Save>,
+) {
     if keys.just_pressed(KeyCode::Space) {
-        state.set(GameState::Level0);
+        play(&mut state, &mut current, &registry, &levels, &save);
         keys.reset(KeyCode::Space);
     }
 }
 
-fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    save: Res<Save>,
+    mut return_to: ResMut<ReturnTo>,
+) {
+    return_to.0 = GameState::MainMenu;
     let font = asset_server.load("Bungee-Regular.ttf");
+    let seed = fastrand::u64(..1_000_000);
+    commands.insert_resource(Seed(seed));
     commands
         .spawn((MainMenu, container_full()))
         .with_children(|parent| {
@@ -49,7 +248,7 @@ fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                         align_content: AlignContent::Center,
                         flex_direction: FlexDirection::Column,
                         justify_content: JustifyContent::Center,
-                        gap: Size::height(Val::Percent(20.)),
+                        gap: Size::height(Val::Percent(8.)),
                         ..default()
                     },
                     background_color: BackgroundColor(colors::light_green()),
@@ -57,9 +256,104 @@ fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                 })
                 .with_children(|parent| {
                     parent.spawn(title_text("Tiler's Trucking Co", font.clone()));
-                    parent.spawn((PlayButton, big_button())).with_children(|p| {
-                        p.spawn(big_button_text("Play", font));
-                    });
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                gap: Size::all(Val::Px(10.)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn((PlayButton, big_button())).with_children(|p| {
+                                p.spawn(big_button_text("Play", font.clone()));
+                            });
+                            parent
+                                .spawn((LevelsButton, big_button()))
+                                .with_children(|p| {
+                                    p.spawn(big_button_text("Levels", font.clone()));
+                                });
+                        });
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                align_items: AlignItems::Center,
+                                flex_direction: FlexDirection::Column,
+                                gap: Size::all(Val::Px(10.)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent
+                                .spawn(NodeBundle {
+                                    style: Style {
+                                        gap: Size::all(Val::Px(10.)),
+                                        ..default()
+                                    },
+                                    ..default()
+                                })
+                                .with_children(|parent| {
+                                    parent.spawn((DailyButton, button())).with_children(|p| {
+                                        p.spawn(button_text("Daily", font.clone()));
+                                    });
+                                    parent.spawn((EndlessButton, button())).with_children(|p| {
+                                        p.spawn(button_text("Endless", font.clone()));
+                                    });
+                                });
+                            parent.spawn(small_text(&daily_text(&save), font.clone()));
+                        });
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                align_items: AlignItems::Center,
+                                flex_direction: FlexDirection::Column,
+                                gap: Size::all(Val::Px(10.)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent
+                                .spawn(NodeBundle {
+                                    style: Style {
+                                        gap: Size::all(Val::Px(10.)),
+                                        ..default()
+                                    },
+                                    ..default()
+                                })
+                                .with_children(|parent| {
+                                    for difficulty in Difficulty::ALL {
+                                        parent
+                                            .spawn((RandomButton(difficulty), button()))
+                                            .with_children(|p| {
+                                                p.spawn(button_text(
+                                                    &difficulty.to_string(),
+                                                    font.clone(),
+                                                ));
+                                            });
+                                    }
+                                });
+                            parent.spawn((SeedText, small_text(&seed_text(seed), font.clone())));
+                        });
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                gap: Size::all(Val::Px(10.)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn((EditorButton, button())).with_children(|p| {
+                                p.spawn(button_text("Editor", font.clone()));
+                            });
+                            for setting in [SettingButton::Music, SettingButton::Sounds] {
+                                parent.spawn((setting, button())).with_children(|p| {
+                                    p.spawn(button_text(&setting.text(&save), font.clone()));
+                                });
+                            }
+                        });
                 });
         });
 }
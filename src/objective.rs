@@ -1,71 +1,175 @@
 use bevy::prelude::*;
+use trucking_core::level::{Deadline, Goods, Throughput};
 
+use crate::colors;
+use crate::deadline::HouseDeadline;
+use crate::endless::Endless;
 use crate::tile::{Border, Tile};
-use crate::world::{PlaceTile, WorldMap};
+use crate::world::{self, PlaceTile, UnplaceTile, WorldMap};
 
 pub struct ObjectivePlugin;
 
 impl Plugin for ObjectivePlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(check_victory).add_event::<Victory>();
+        app.init_resource::<Deliveries>()
+            .add_system(
+                check_victory
+                    .after(world::place_tile)
+                    .after(world::unplace_tile),
+            )
+            .add_event::<Victory>()
+            .add_event::<CheckObjectives>();
     }
 }
 
 pub struct Victory;
 
-fn check_victory(
-    event: EventReader<PlaceTile>,
+/// Check the deliveries and the limits again when something other than a
+/// placement changed them
+pub struct CheckObjectives;
+
+/// Which houses can currently be reached by road from a distribution center
+#[derive(Resource, Default, Debug)]
+pub struct Deliveries {
+    pub connected: Vec<(i32, i32)>,
+    pub unreached: Vec<(i32, i32)>,
+    /// Houses that gave up waiting
+    pub failed: Vec<(i32, i32)>,
+    /// Whether the roads from depots with different goods must not meet
+    pub separate: bool,
+    /// The roads from depots with different goods have met
+    pub mixed: bool,
+    /// The parcels that have to be delivered in time, if any
+    pub throughput: Option<Throughput>,
+    /// Parcels delivered so far
+    pub parcels: usize,
+}
+
+impl Deliveries {
+    pub fn total(&self) -> usize {
+        self.connected.len() + self.unreached.len()
+    }
+
+    /// Every house is connected, or enough parcels were delivered when the
+    /// level counts them. A house that gave up can no longer be delivered to.
+    pub fn complete(&self) -> bool {
+        let delivered = match self.throughput {
+            Some(t) => self.parcels >= t.parcels,
+            None => self.unreached.is_empty(),
+        };
+        delivered && !self.connected.is_empty() && !self.mixed && self.failed.is_empty()
+    }
+}
+
+/// The board can be changed until the level is won, endless levels go on
+pub fn playing(deliveries: Res<Deliveries>, endless: Res<Endless>) -> bool {
+    endless.active || !deliveries.complete()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn check_victory(
+    mut event: EventReader<PlaceTile>,
+    mut removed: EventReader<UnplaceTile>,
+    mut check: EventReader<CheckObjectives>,
     mut victory: EventWriter<Victory>,
+    mut deliveries: ResMut<Deliveries>,
     wm: Res<WorldMap>,
-    query: Query<&ObjectiveTile>,
-    tiles: Query<&Tile>,
+    endless: Res<Endless>,
+    query: Query<(&ObjectiveTile, Option<&Failed>)>,
 ) {
-    if !event.is_empty() {
-        let connected = !query.is_empty()
-            && query.iter().all(|ot| {
-                if let Some(e) = wm.get_tile(ot.x, ot.y) {
-                    let tile = tiles.get(e).expect("Could not find tile");
-                    let top = tile.top == Border::Road
-                        && wm
-                            .get_tile(ot.x, ot.y + 1)
-                            .map_or(false, |e| tiles.get(e).expect("Could not find tile").placed);
-                    let bottom = tile.bottom == Border::Road
-                        && wm
-                            .get_tile(ot.x, ot.y - 1)
-                            .map_or(false, |e| tiles.get(e).expect("Could not find tile").placed);
-                    let right = tile.right == Border::Road
-                        && wm
-                            .get_tile(ot.x + 1, ot.y)
-                            .map_or(false, |e| tiles.get(e).expect("Could not find tile").placed);
-                    let left = tile.left == Border::Road
-                        && wm
-                            .get_tile(ot.x - 1, ot.y)
-                            .map_or(false, |e| tiles.get(e).expect("Could not find tile").placed);
-                    top || bottom || right || left
-                } else {
-                    false
-                }
+    if !event.is_empty() || !removed.is_empty() || !check.is_empty() {
+        let placed = !event.is_empty() || !check.is_empty();
+        event.clear();
+        removed.clear();
+        check.clear();
+        let networks: Vec<_> = query
+            .iter()
+            .filter(|(ot, _)| ot.kind == ObjectiveKind::Depot)
+            .map(|(ot, _)| (ot.goods, wm.board.reachable((ot.x, ot.y))))
+            .collect();
+        let mixed = deliveries.separate
+            && networks.iter().any(|(goods, network)| {
+                query.iter().any(|(ot, _)| {
+                    ot.kind == ObjectiveKind::Depot
+                        && goods.is_some()
+                        && ot.goods.is_some()
+                        && ot.goods != *goods
+                        && network.contains(&(ot.x, ot.y))
+                })
             });
-        if connected {
+        let (failed, waiting): (Vec<_>, Vec<_>) = query
+            .iter()
+            .filter(|(ot, _)| ot.kind == ObjectiveKind::House)
+            .partition(|(_, failed)| failed.is_some());
+        let (connected, unreached): (Vec<_>, Vec<_>) = waiting.into_iter().partition(|(ot, _)| {
+            networks
+                .iter()
+                .any(|(goods, network)| ot.takes(*goods) && network.contains(&(ot.x, ot.y)))
+        });
+        *deliveries = Deliveries {
+            connected: connected.into_iter().map(|(ot, _)| (ot.x, ot.y)).collect(),
+            unreached: unreached.into_iter().map(|(ot, _)| (ot.x, ot.y)).collect(),
+            failed: failed.into_iter().map(|(ot, _)| (ot.x, ot.y)).collect(),
+            separate: deliveries.separate,
+            mixed,
+            throughput: deliveries.throughput,
+            parcels: deliveries.parcels,
+        };
+        if placed && deliveries.complete() && !endless.active {
             victory.send(Victory);
         }
     }
 }
 
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ObjectiveKind {
+    Depot,
+    House,
+}
+
+/// A house that gave up waiting, the level can no longer be completed
+#[derive(Component)]
+pub struct Failed;
+
 #[derive(Component, Debug)]
 pub struct ObjectiveTile {
     x: i32,
     y: i32,
+    kind: ObjectiveKind,
+    goods: Option<Goods>,
 }
 
-pub fn setup_start_tile(
-    x: i32,
-    y: i32,
-    tile: Tile,
-    cmds: &mut Commands,
-    asset_server: &Res<AssetServer>,
-    tile_placed: &mut EventWriter<PlaceTile>,
-) {
+impl ObjectiveTile {
+    pub fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    pub fn is_depot(&self) -> bool {
+        self.kind == ObjectiveKind::Depot
+    }
+
+    pub fn goods(&self) -> Option<Goods> {
+        self.goods
+    }
+
+    /// Whether a house can be delivered to from a depot with the goods
+    pub fn takes(&self, goods: Option<Goods>) -> bool {
+        self.goods.is_none_or(|g| goods == Some(g))
+    }
+}
+
+pub fn goods_color(goods: Option<Goods>) -> Color {
+    match goods {
+        None => Color::WHITE,
+        Some(Goods::Red) => colors::red(),
+        Some(Goods::Blue) => colors::blue(),
+        Some(Goods::Yellow) => colors::yellow(),
+        Some(Goods::Purple) => colors::purple(),
+    }
+}
+
+/// Place the building on a tile, pushed away from its roads
+pub fn building_transform(x: i32, y: i32, tile: &Tile, distance: f32) -> Transform {
     let mut offset = Vec3::ZERO;
     if tile.top == Border::Road {
         offset.y -= 1.;
@@ -79,16 +183,34 @@ pub fn setup_start_tile(
     if tile.left == Border::Road {
         offset.x += 1.;
     }
+    Transform::from_translation(
+        Vec3::new(x as f32, y as f32, 1.) + offset.normalize_or_zero() * distance,
+    )
+}
+
+pub fn setup_start_tile(
+    x: i32,
+    y: i32,
+    tile: Tile,
+    goods: Option<Goods>,
+    cmds: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    tile_placed: &mut EventWriter<PlaceTile>,
+) {
     cmds.spawn((
-        ObjectiveTile { x, y },
+        ObjectiveTile {
+            x,
+            y,
+            kind: ObjectiveKind::Depot,
+            goods,
+        },
         SpriteBundle {
             sprite: Sprite {
+                color: goods_color(goods),
                 custom_size: Some(Vec2::ONE),
                 ..default()
             },
-            transform: Transform::from_translation(
-                Vec3::new(x as f32, y as f32, 1.) + offset.normalize() * 0.25,
-            ),
+            transform: building_transform(x, y, &tile, 0.25),
             texture: asset_server.load("distribution_center.png"),
             ..default()
         },
@@ -96,40 +218,37 @@ pub fn setup_start_tile(
     tile_placed.send(PlaceTile::new(x, y, tile));
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn setup_end_tile(
     x: i32,
     y: i32,
     tile: Tile,
+    goods: Option<Goods>,
+    deadline: Option<Deadline>,
     cmds: &mut Commands,
     asset_server: &Res<AssetServer>,
     tile_placed: &mut EventWriter<PlaceTile>,
 ) {
-    let mut offset = Vec3::ZERO;
-    if tile.top == Border::Road {
-        offset.y -= 1.;
-    }
-    if tile.right == Border::Road {
-        offset.x -= 1.;
-    }
-    if tile.bottom == Border::Road {
-        offset.y += 1.;
-    }
-    if tile.left == Border::Road {
-        offset.x += 1.;
-    }
-    cmds.spawn((
-        ObjectiveTile { x, y },
+    let mut entity = cmds.spawn((
+        ObjectiveTile {
+            x,
+            y,
+            kind: ObjectiveKind::House,
+            goods,
+        },
         SpriteBundle {
             sprite: Sprite {
+                color: goods_color(goods),
                 custom_size: Some(Vec2::ONE),
                 ..default()
             },
-            transform: Transform::from_translation(
-                Vec3::new(x as f32, y as f32, 1.) + offset.normalize() * 0.3,
-            ),
+            transform: building_transform(x, y, &tile, 0.3),
             texture: asset_server.load("house.png"),
             ..default()
         },
     ));
+    if let Some(deadline) = deadline {
+        entity.insert(HouseDeadline::new(deadline));
+    }
     tile_placed.send(PlaceTile::new_slient(x, y, tile));
 }
@@ -0,0 +1,338 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use trucking_core::generator;
+use trucking_core::replay::{Action, Replay};
+
+use crate::deck::{Deck, TopTileRotated};
+use crate::history::{self, History};
+use crate::levels::{ActiveLevel, GameUI, LevelAsset, LevelRegistry};
+use crate::limits::Limits;
+use crate::objective::{Deliveries, Victory};
+use crate::save::storage;
+use crate::state::{CurrentLevel, GameState};
+use crate::tile;
+use crate::ui::button_text;
+use crate::world::{PlaceTile, UnplaceTile, WorldMap};
+
+/// The replay of the last session, for bug reports
+const LAST_REPLAY: &str = "replays/last.replay";
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Recording>()
+            .init_resource::<Playback>()
+            .add_event::<ActionTaken>()
+            .add_system(load_dropped_replay)
+            .add_system(end_session.in_schedule(OnExit(GameState::InGame)))
+            .add_systems(
+                (record_actions, save_solution)
+                    .distributive_run_if(not_replaying)
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_systems(
+                (play_replay, replay_controls, update_replay_text)
+                    .distributive_run_if(is_replaying)
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
+
+/// Sent by the player controls, rotations are recorded from `TopTileRotated`
+pub struct ActionTaken(pub Action);
+
+/// The session that is being played
+#[derive(Resource, Default)]
+pub struct Recording {
+    pub replay: Option<Replay>,
+    started: f32,
+}
+
+/// The replay that is being watched, or that will be watched when the level starts
+#[derive(Resource, Default)]
+pub struct Playback {
+    pending: Option<Replay>,
+    replay: Option<Replay>,
+    index: usize,
+    time: f32,
+    speed: f32,
+    paused: bool,
+    message: Option<String>,
+}
+
+impl Playback {
+    /// Watch a replay when the level is entered next time
+    pub fn watch(&mut self, replay: Replay) {
+        self.pending = Some(replay);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.replay.is_some()
+    }
+}
+
+pub fn not_replaying(playback: Res<Playback>) -> bool {
+    !playback.is_running()
+}
+
+pub fn is_replaying(playback: Res<Playback>) -> bool {
+    playback.is_running()
+}
+
+#[derive(Component)]
+struct ReplayText;
+
+/// Everything that the player controls can change
+#[derive(SystemParam)]
+struct Player<'w> {
+    deck: ResMut<'w, Deck>,
+    history: ResMut<'w, History>,
+    limits: Res<'w, Limits>,
+    deliveries: Res<'w, Deliveries>,
+    wm: Res<'w, WorldMap>,
+    place: EventWriter<'w, PlaceTile>,
+    unplace: EventWriter<'w, UnplaceTile>,
+    rotated: EventWriter<'w, TopTileRotated>,
+}
+
+impl Player<'_> {
+    fn apply(&mut self, action: &Action) -> bool {
+        match action {
+            Action::Rotate(tile) => self.deck.rotate_to(tile, &mut self.rotated),
+            Action::Skip => self.deck.skip_tile(&self.limits, &mut self.place),
+            Action::Hold => self.deck.hold_tile(&self.limits, &mut self.place),
+            Action::Place(x, y) => tile::place_top(
+                *x,
+                *y,
+                &mut self.deck,
+                &mut self.history,
+                &self.wm,
+                &mut self.place,
+            ),
+            Action::Undo => history::undo(
+                &mut self.history,
+                &mut self.deck,
+                &self.deliveries,
+                &mut self.unplace,
+            ),
+            Action::Redo => history::redo(&mut self.history, &mut self.deck, &mut self.place),
+        }
+    }
+}
+
+/// Start recording, or start the pending replay, once the deck is ready
+pub fn start_session(
+    mut commands: Commands,
+    mut recording: ResMut<Recording>,
+    mut playback: ResMut<Playback>,
+    mut deck: ResMut<Deck>,
+    level: ActiveLevel,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+) {
+    let Some(level) = level.get() else {
+        return;
+    };
+    if let Some(replay) = playback.pending.take() {
+        deck.set_rng_state(replay.seed);
+        *playback = Playback {
+            replay: Some(replay),
+            speed: 1.,
+            ..default()
+        };
+        recording.replay = None;
+        let font = asset_server.load("Bungee-Regular.ttf");
+        let mut text = button_text("", font);
+        text.style.position = UiRect {
+            bottom: Val::Px(10.),
+            ..default()
+        };
+        text.style.position_type = PositionType::Absolute;
+        text.style.align_self = AlignSelf::Center;
+        text.text.alignment = TextAlignment::Center;
+        commands.spawn((GameUI, ReplayText, text));
+    } else {
+        recording.replay = Some(Replay::new(&level.name, deck.rng_state()));
+        recording.started = time.elapsed_seconds();
+    }
+}
+
+fn end_session(recording: Res<Recording>, mut playback: ResMut<Playback>) {
+    if let Some(replay) = &recording.replay {
+        if !replay.actions.is_empty() {
+            write_replay(LAST_REPLAY, replay);
+        }
+    }
+    playback.replay = None;
+}
+
+fn write_replay(name: &str, replay: &Replay) {
+    if let Err(e) = storage::write(name, &replay.to_string()) {
+        warn!("Could not write the replay: {}", e);
+    }
+}
+
+fn record_actions(
+    mut recording: ResMut<Recording>,
+    mut rotated: EventReader<TopTileRotated>,
+    mut actions: EventReader<ActionTaken>,
+    time: Res<Time>,
+) {
+    let seconds = time.elapsed_seconds() - recording.started;
+    if let Some(replay) = &mut recording.replay {
+        for TopTileRotated(tile) in rotated.iter() {
+            replay.actions.push((seconds, Action::Rotate(tile.clone())));
+        }
+        for ActionTaken(action) in actions.iter() {
+            replay.actions.push((seconds, action.clone()));
+        }
+    }
+}
+
+/// Keep the replay of every won level, to share the solution
+fn save_solution(mut event: EventReader<Victory>, recording: Res<Recording>) {
+    if event.is_empty() {
+        return;
+    }
+    event.clear();
+    if let Some(replay) = &recording.replay {
+        let name: String = replay
+            .level
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        write_replay(&format!("replays/{}.replay", name), replay);
+    }
+}
+
+/// Take the next action when it is time, at most one per frame so that the
+/// board is up to date for every action
+fn play_replay(mut playback: ResMut<Playback>, mut player: Player, time: Res<Time>) {
+    if playback.paused {
+        return;
+    }
+    playback.time += time.delta_seconds() * playback.speed;
+    step(&mut playback, &mut player);
+}
+
+fn step(playback: &mut Playback, player: &mut Player) {
+    let Some(replay) = &playback.replay else {
+        return;
+    };
+    let Some((at, action)) = replay.actions.get(playback.index) else {
+        playback.paused = true;
+        return;
+    };
+    if *at > playback.time {
+        return;
+    }
+    if !player.apply(action) {
+        playback.message = Some(format!("Could not {} at {:.1}s", action, at));
+        playback.index = replay.actions.len();
+        playback.paused = true;
+        return;
+    }
+    playback.index += 1;
+}
+
+fn replay_controls(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut playback: ResMut<Playback>,
+    mut player: Player,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        keys.reset(KeyCode::Space);
+        playback.paused = !playback.paused;
+    }
+    if keys.just_pressed(KeyCode::Right) {
+        keys.reset(KeyCode::Right);
+        playback.paused = true;
+        let next = playback
+            .replay
+            .as_ref()
+            .and_then(|r| r.actions.get(playback.index))
+            .map(|(at, _)| *at);
+        if let Some(at) = next {
+            playback.time = playback.time.max(at);
+            step(&mut playback, &mut player);
+        }
+    }
+    if keys.just_pressed(KeyCode::Up) {
+        keys.reset(KeyCode::Up);
+        playback.speed = (playback.speed * 2.).min(16.);
+    }
+    if keys.just_pressed(KeyCode::Down) {
+        keys.reset(KeyCode::Down);
+        playback.speed = (playback.speed * 0.5).max(0.25);
+    }
+}
+
+fn update_replay_text(playback: Res<Playback>, mut query: Query<&mut Text, With<ReplayText>>) {
+    if !playback.is_changed() {
+        return;
+    }
+    if let (Ok(mut text), Some(replay)) = (query.get_single_mut(), &playback.replay) {
+        let status = match &playback.message {
+            Some(message) => message.clone(),
+            None if playback.paused => "Paused".to_string(),
+            None => format!("{}x", playback.speed),
+        };
+        text.sections[0].value = format!(
+            "Replay {}/{} - {}\nSPACE play/pause, RIGHT step, UP/DOWN speed",
+            playback.index,
+            replay.actions.len(),
+            status
+        );
+    }
+}
+
+/// Watch a replay file that is dropped onto the window
+#[allow(clippy::too_many_arguments)]
+fn load_dropped_replay(
+    state: Res<State<GameState>>,
+    mut events: EventReader<FileDragAndDrop>,
+    mut assets: ResMut<Assets<LevelAsset>>,
+    mut registry: ResMut<LevelRegistry>,
+    mut current: ResMut<CurrentLevel>,
+    mut playback: ResMut<Playback>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if state.0 == GameState::Editor {
+        return;
+    }
+    for ev in events.iter() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = ev else {
+            continue;
+        };
+        if path_buf.extension().is_none_or(|e| e != "replay") {
+            continue;
+        }
+        let replay = match std::fs::read_to_string(path_buf) {
+            Ok(text) => match Replay::parse(&text) {
+                Ok(replay) => replay,
+                Err(e) => {
+                    warn!("Could not parse {}: {}", path_buf.display(), e);
+                    continue;
+                }
+            },
+            Err(e) => {
+                warn!("Could not read {}: {}", path_buf.display(), e);
+                continue;
+            }
+        };
+        // Random levels are not registered until they are generated again
+        let index = registry.find(&assets, &replay.level).or_else(|| {
+            let level = generator::regenerate(&replay.level)?;
+            Some(registry.register(assets.add(LevelAsset(level))))
+        });
+        match index {
+            Some(index) => {
+                current.0 = index;
+                playback.watch(replay);
+                next_state.set(GameState::InGame);
+            }
+            None => warn!("Could not find the level '{}'", replay.level),
+        }
+    }
+}
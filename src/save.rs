@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+use trucking_core::save::Settings;
+use trucking_core::{SaveData, Score};
+
+const SAVE_FILE: &str = "save.txt";
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Save::load());
+    }
+}
+
+/// The save file, every change is written right away
+#[derive(Resource, Default, Deref)]
+pub struct Save(SaveData);
+
+impl Save {
+    /// Load the save, starting over if it is missing or cannot be read
+    pub fn load() -> Self {
+        match storage::read(SAVE_FILE) {
+            Ok(Some(text)) => match SaveData::parse(&text) {
+                Ok(data) => Self(data),
+                Err(e) => {
+                    warn!("Ignoring the save file, {}", e);
+                    Self::default()
+                }
+            },
+            Ok(None) => Self::default(),
+            Err(e) => {
+                warn!("Could not read the save file: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn record(&mut self, name: &str, score: &Score, stars: usize) {
+        if self.0.record(name, score, stars) {
+            self.write();
+        }
+    }
+
+    pub fn record_daily(&mut self, day: i64, score: &Score, stars: usize) {
+        if self.0.record_daily(day, score, stars) {
+            self.write();
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.0.settings = settings;
+        self.write();
+    }
+
+    fn write(&self) {
+        if let Err(e) = storage::write(SAVE_FILE, &self.0.to_string()) {
+            warn!("Could not write the save file: {}", e);
+        }
+    }
+}
+
+/// Files in the data directory, or in local storage on the web
+#[cfg(not(target_arch = "wasm32"))]
+pub mod storage {
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+
+    fn path(name: &str) -> Option<PathBuf> {
+        dirs::data_dir().map(|d| d.join("tilers_trucking_co").join(name))
+    }
+
+    pub fn read(name: &str) -> Result<Option<String>, String> {
+        let Some(path) = path(name) else {
+            return Ok(None);
+        };
+        match std::fs::read_to_string(path) {
+            Ok(text) => Ok(Some(text)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub fn write(name: &str, text: &str) -> Result<(), String> {
+        let path = path(name).ok_or("no data directory")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        // Replace the old save in one go so that it is never half written
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, text).map_err(|e| e.to_string())?;
+        std::fs::rename(tmp, path).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub mod storage {
+    fn key(name: &str) -> String {
+        format!("tilers_trucking_co/{}", name)
+    }
+
+    fn local_storage() -> Result<web_sys::Storage, String> {
+        web_sys::window()
+            .and_then(|w| w.local_storage().ok().flatten())
+            .ok_or_else(|| "local storage is not available".to_string())
+    }
+
+    pub fn read(name: &str) -> Result<Option<String>, String> {
+        local_storage()?
+            .get_item(&key(name))
+            .map_err(|_| "could not read from local storage".to_string())
+    }
+
+    pub fn write(name: &str, text: &str) -> Result<(), String> {
+        local_storage()?
+            .set_item(&key(name), text)
+            .map_err(|_| "could not write to local storage".to_string())
+    }
+}
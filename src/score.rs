@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+use trucking_core::daily;
+
+use crate::deck::{Deck, TopTileRotated};
+use crate::history::History;
+use crate::levels::ActiveLevel;
+use crate::objective::{Deliveries, Victory};
+use crate::replay::Playback;
+use crate::save::Save;
+use crate::state::GameState;
+use crate::trucks;
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .add_system(track_score.in_set(OnUpdate(GameState::InGame)))
+            .add_system(
+                finish_score
+                    .after(trucks::start_trucks)
+                    .in_set(OnUpdate(GameState::InGame)),
+            );
+    }
+}
+
+/// Rotations and time are tracked while playing, the tiles are counted at the end
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct Score(pub trucking_core::Score);
+
+impl Score {
+    pub fn count_tiles(&mut self, deck: &Deck, history: &History) {
+        self.placed = history.placed();
+        self.skipped = deck.skipped();
+        self.undos = history.undos;
+    }
+}
+
+fn track_score(
+    mut score: ResMut<Score>,
+    mut rotated: EventReader<TopTileRotated>,
+    deliveries: Res<Deliveries>,
+    time: Res<Time>,
+) {
+    let rotations = rotated.iter().count();
+    if !deliveries.complete() {
+        score.rotations += rotations;
+        score.seconds += time.delta_seconds();
+    }
+}
+
+/// Count the tiles and keep the best result of campaign and daily levels
+pub fn finish_score(
+    mut event: EventReader<Victory>,
+    mut score: ResMut<Score>,
+    mut save: ResMut<Save>,
+    deck: Res<Deck>,
+    history: Res<History>,
+    playback: Res<Playback>,
+    level: ActiveLevel,
+) {
+    if event.is_empty() {
+        return;
+    }
+    event.clear();
+    score.count_tiles(&deck, &history);
+    if playback.is_running() {
+        return;
+    }
+    if let Some(data) = level.get() {
+        let stars = score.stars(data.star_limits());
+        if level.in_campaign() {
+            save.record(&data.name, &score, stars);
+        } else if let Some(day) = daily::parse_name(&data.name) {
+            save.record_daily(day, &score, stars);
+        }
+    }
+}
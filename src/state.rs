@@ -4,30 +4,21 @@ use bevy::prelude::*;
 pub enum GameState {
     #[default]
     MainMenu,
-    Level0,
-    Level1,
-    Level2,
-    Level3,
-    Level4,
-    Level5,
-    Level6,
-    Level7,
-    LevelTest,
+    InGame,
+    Editor,
+    LevelSelect,
 }
 
-impl GameState {
-    pub fn next_level(&self) -> Self {
-        match self {
-            GameState::MainMenu => GameState::Level0,
-            GameState::Level0 => GameState::Level1,
-            GameState::Level1 => GameState::Level2,
-            GameState::Level2 => GameState::Level3,
-            GameState::Level3 => GameState::Level4,
-            GameState::Level4 => GameState::Level5,
-            GameState::Level5 => GameState::Level6,
-            GameState::Level6 => GameState::Level7,
-            GameState::Level7 => GameState::MainMenu,
-            _ => GameState::MainMenu,
-        }
+/// Index of the level being played in the `LevelRegistry`
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurrentLevel(pub usize);
+
+/// Where to go when leaving a level
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReturnTo(pub GameState);
+
+impl Default for ReturnTo {
+    fn default() -> Self {
+        Self(GameState::MainMenu)
     }
 }
@@ -1,120 +1,30 @@
 use crate::camera::cursor_to_world;
 use crate::colors;
 use crate::deck::Deck;
-use crate::world::PlaceTile;
+use crate::hint::Hint;
+use crate::history::History;
+use crate::limits::Limits;
+use crate::objective;
+use crate::replay::{not_replaying, ActionTaken};
+use crate::world::{PlaceTile, WorldMap};
 use bevy::prelude::*;
+use bevy::render::texture::DEFAULT_IMAGE_HANDLE;
 use bevy::utils::HashMap;
 use std::f32::consts::PI;
+use trucking_core::replay::Action;
+use trucking_core::Terrain;
 
-#[derive(Default, Component, Debug, Clone)]
-pub struct Tile {
-    pub placed: bool,
-    pub top: Border,
-    pub right: Border,
-    pub bottom: Border,
-    pub left: Border,
-}
+pub use trucking_core::tile::{Border, Tile};
 
-#[derive(Default, PartialEq, Clone, Copy, Debug)]
-pub enum Border {
-    #[default]
-    Any,
-    Empty,
-    Road,
-}
-
-impl Tile {
-    pub fn has_road(&self) -> bool {
-        self.top == Border::Road
-            || self.right == Border::Road
-            || self.bottom == Border::Road
-            || self.left == Border::Road
-    }
-
-    pub fn empty() -> Self {
-        Self {
-            placed: false,
-            top: Border::Empty,
-            right: Border::Empty,
-            bottom: Border::Empty,
-            left: Border::Empty,
-        }
-    }
-
-    pub fn create(pattern: &'static str) -> Self {
-        let mut new = Self::empty();
-        for v in pattern.as_bytes() {
-            match v {
-                b't' => new.top = Border::Road,
-                b'r' => new.right = Border::Road,
-                b'b' => new.bottom = Border::Road,
-                b'l' => new.left = Border::Road,
-                _ => {}
-            }
-        }
-        new
-    }
-
-    pub fn rotate270(&self) -> Self {
-        Self {
-            placed: self.placed,
-            top: self.right,
-            right: self.bottom,
-            bottom: self.left,
-            left: self.top,
-        }
-    }
-
-    pub fn rotate90(&self) -> Self {
-        Self {
-            placed: self.placed,
-            top: self.left,
-            right: self.top,
-            bottom: self.right,
-            left: self.bottom,
-        }
-    }
-
-    pub fn rotate180(&self) -> Self {
-        Self {
-            placed: self.placed,
-            top: self.bottom,
-            right: self.left,
-            bottom: self.top,
-            left: self.right,
-        }
-    }
-
-    pub fn placeable(&self, other: &Self) -> bool {
-        !other.placed
-            && (other.top == Border::Any || self.top == other.top)
-            && (other.right == Border::Any || self.right == other.right)
-            && (other.bottom == Border::Any || self.bottom == other.bottom)
-            && (other.left == Border::Any || self.left == other.left)
-    }
-}
-
-impl From<&Tile> for u32 {
-    fn from(value: &Tile) -> Self {
-        (value.top as u32)
-            + (value.right as u32) * (1 << 4)
-            + (value.bottom as u32) * (1 << 8)
-            + (value.left as u32) * (1 << 16)
-    }
-}
-
-impl From<Tile> for u32 {
-    fn from(value: Tile) -> Self {
-        (value.top as u32)
-            + (value.right as u32) * (1 << 4)
-            + (value.bottom as u32) * (1 << 8)
-            + (value.left as u32) * (1 << 16)
-    }
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Cell {
+    pub x: i32,
+    pub y: i32,
 }
 
 #[derive(Bundle)]
 pub struct TileBundle {
-    tile: Tile,
+    cell: Cell,
     sprite: SpriteBundle,
 }
 
@@ -129,7 +39,7 @@ impl TileBundle {
             ..default()
         };
         Self {
-            tile: Tile::default(),
+            cell: Cell { x, y },
             sprite: SpriteBundle {
                 sprite,
                 transform,
@@ -167,26 +77,59 @@ impl SelectTileBundle {
     }
 }
 
+/// Place the top tile of the deck and draw the next one
+pub fn place_top(
+    x: i32,
+    y: i32,
+    deck: &mut Deck,
+    history: &mut History,
+    wm: &WorldMap,
+    event: &mut EventWriter<PlaceTile>,
+) -> bool {
+    let Some(tile) = deck.get_top().cloned() else {
+        return false;
+    };
+    if !wm.board.can_place(x, y, &tile) {
+        return false;
+    }
+    let hold_used = deck.hold_used();
+    event.send(PlaceTile::new(x, y, tile.clone()));
+    deck.place();
+    history.record(x, y, tile, hold_used);
+    true
+}
+
+#[allow(clippy::too_many_arguments)]
 fn update_select_tile(
     mut deck: ResMut<Deck>,
-    mut query: Query<(&mut Sprite, &Parent, &GlobalTransform), With<SelectTile>>,
-    tile_query: Query<&Tile>,
+    mut history: ResMut<History>,
+    hint: Res<Hint>,
+    limits: Res<Limits>,
+    mut query: Query<(&mut Sprite, &GlobalTransform), With<SelectTile>>,
+    wm: Res<WorldMap>,
     input: Res<Input<MouseButton>>,
     windows: Query<&Window>,
     cameras: Query<(&Camera, &GlobalTransform), With<Camera>>,
     mut event: EventWriter<PlaceTile>,
+    mut actions: EventWriter<ActionTaken>,
 ) {
     let normal = colors::yellow();
     let disabled = colors::dark_green();
+    if limits.defeated {
+        for (mut s, _) in query.iter_mut() {
+            s.color = disabled;
+        }
+        return;
+    }
     if let Some(tile) = deck.get_top() {
         let cursor = cursor_to_world(windows, cameras).unwrap_or(Vec2 {
             x: f32::MAX,
             y: f32::MAX,
         });
-        for (mut s, p, tr) in query.iter_mut() {
-            let t = tile_query.get(p.get()).expect("Could not find parent");
-            if tile.placeable(t) {
-                let pos = tr.translation().truncate();
+        for (mut s, tr) in query.iter_mut() {
+            let pos = tr.translation().truncate();
+            let ipos = pos.round().as_ivec2();
+            if wm.board.can_place(ipos.x, ipos.y, tile) {
                 if (pos - cursor).abs().max_element() < 0.5 {
                     if input.pressed(MouseButton::Left) {
                         s.color = colors::dark();
@@ -194,12 +137,12 @@ fn update_select_tile(
                         s.color = colors::orange();
                     }
                     if input.just_released(MouseButton::Left) {
-                        let ipos = pos.as_ivec2();
-                        event.send(PlaceTile::new(ipos.x, ipos.y, tile.clone()));
-                        return {
-                            deck.next();
-                        };
+                        place_top(ipos.x, ipos.y, &mut deck, &mut history, &wm, &mut event);
+                        actions.send(ActionTaken(Action::Place(ipos.x, ipos.y)));
+                        return;
                     }
+                } else if hint.target == Some((ipos.x, ipos.y)) {
+                    s.color = colors::blue();
                 } else {
                     s.color = normal;
                 }
@@ -211,37 +154,65 @@ fn update_select_tile(
 }
 
 #[derive(Resource, Default)]
-pub struct TileServer(HashMap<u32, Handle<Image>>);
+pub struct TileServer {
+    tiles: HashMap<u32, Handle<Image>>,
+    terrain: HashMap<Terrain, Handle<Image>>,
+}
 
 impl TileServer {
     pub fn find_texture(&self, tile: &Tile) -> (Handle<Image>, f32) {
-        if let Some(s) = self.0.get(&tile.into()) {
+        if let Some(s) = self.tiles.get(&tile.into()) {
             return (s.clone(), 0.);
         }
-        if let Some(s) = self.0.get(&tile.rotate90().into()) {
+        if let Some(s) = self.tiles.get(&tile.rotate90().into()) {
             return (s.clone(), PI * 0.5);
         }
-        if let Some(s) = self.0.get(&tile.rotate180().into()) {
+        if let Some(s) = self.tiles.get(&tile.rotate180().into()) {
             return (s.clone(), PI);
         }
-        if let Some(s) = self.0.get(&tile.rotate270().into()) {
+        if let Some(s) = self.tiles.get(&tile.rotate270().into()) {
             return (s.clone(), -PI * 0.5);
         }
         panic!("Could not find a tile: {:?}", tile);
     }
 
+    /// The texture and color of an empty cell, grass is just a colored square
+    pub fn terrain_texture(&self, terrain: Terrain) -> (Handle<Image>, Color) {
+        match self.terrain.get(&terrain) {
+            Some(h) => (h.clone(), Color::WHITE),
+            None => (DEFAULT_IMAGE_HANDLE.typed(), colors::light_green()),
+        }
+    }
+
     pub fn load_assets(asset_server: Res<AssetServer>, mut ts: ResMut<TileServer>) {
-        ts.0.insert(Tile::create("t").into(), asset_server.load("tile_t.png"));
-        ts.0.insert(Tile::create("tr").into(), asset_server.load("tile_tr.png"));
-        ts.0.insert(Tile::create("tb").into(), asset_server.load("tile_tb.png"));
-        ts.0.insert(
+        ts.tiles
+            .insert(Tile::create("t").into(), asset_server.load("tile_t.png"));
+        ts.tiles
+            .insert(Tile::create("tr").into(), asset_server.load("tile_tr.png"));
+        ts.tiles
+            .insert(Tile::create("tb").into(), asset_server.load("tile_tb.png"));
+        ts.tiles.insert(
             Tile::create("trb").into(),
             asset_server.load("tile_trb.png"),
         );
-        ts.0.insert(
+        ts.tiles.insert(
             Tile::create("trbl").into(),
             asset_server.load("tile_trbl.png"),
         );
+        ts.tiles.insert(
+            Tile::create("tb=").into(),
+            asset_server.load("tile_bridge.png"),
+        );
+        ts.tiles.insert(
+            Tile::create("trbl=").into(),
+            asset_server.load("tile_overpass.png"),
+        );
+        ts.terrain
+            .insert(Terrain::Forest, asset_server.load("terrain_forest.png"));
+        ts.terrain
+            .insert(Terrain::Hills, asset_server.load("terrain_hills.png"));
+        ts.terrain
+            .insert(Terrain::Water, asset_server.load("terrain_water.png"));
     }
 }
 
@@ -251,6 +222,10 @@ impl Plugin for TilePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<TileServer>()
             .add_startup_system(TileServer::load_assets)
-            .add_system(update_select_tile);
+            .add_system(
+                update_select_tile
+                    .run_if(not_replaying)
+                    .run_if(objective::playing),
+            );
     }
 }
@@ -0,0 +1,187 @@
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use trucking_core::traffic::{self, Stop};
+
+use crate::colors;
+use crate::levels::ActiveLevel;
+use crate::limits::Limits;
+use crate::objective::{self, CheckObjectives, Deliveries, ObjectiveTile};
+use crate::state::GameState;
+use crate::tile::Cell;
+use crate::trucks::{pulse, steer, truck_sprite};
+use crate::world::WorldMap;
+
+/// How quickly the heatmap follows the traffic
+const HEAT_RATE: f32 = 3.;
+
+pub struct TrafficPlugin;
+
+impl Plugin for TrafficPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Traffic>().add_systems(
+            (
+                run_traffic.after(objective::check_victory),
+                draw_heatmap.after(run_traffic),
+            )
+                .distributive_run_if(is_traffic)
+                .in_set(OnUpdate(GameState::InGame)),
+        );
+    }
+}
+
+/// The parcels and trucks of a level with a throughput goal, inactive for other levels
+#[derive(Resource, Default)]
+pub struct Traffic {
+    pub active: bool,
+    sim: traffic::Traffic,
+    /// How congested each road tile looks, trailing the actual traffic
+    heat: HashMap<(i32, i32), f32>,
+}
+
+impl Traffic {
+    pub fn seconds(&self) -> f32 {
+        self.sim.seconds
+    }
+}
+
+/// The sprite of a truck in the traffic
+#[derive(Component)]
+pub struct TrafficTruck {
+    id: usize,
+    start: Vec2,
+}
+
+fn is_traffic(traffic: Res<Traffic>) -> bool {
+    traffic.active
+}
+
+pub fn start_traffic(mut traffic: ResMut<Traffic>, level: ActiveLevel) {
+    if let Some(level) = level.get().filter(|l| l.throughput.is_some()) {
+        *traffic = Traffic {
+            active: true,
+            sim: traffic::Traffic::new(level.capacity.unwrap_or(traffic::DEFAULT_CAPACITY)),
+            ..default()
+        };
+    }
+}
+
+/// Drive the trucks, count the delivered parcels, and end the level when
+/// enough have arrived or the time runs out
+#[allow(clippy::too_many_arguments)]
+fn run_traffic(
+    mut cmds: Commands,
+    mut traffic: ResMut<Traffic>,
+    mut deliveries: ResMut<Deliveries>,
+    mut limits: ResMut<Limits>,
+    mut check: EventWriter<CheckObjectives>,
+    mut trucks: Query<(Entity, &TrafficTruck, &mut Transform)>,
+    objectives: Query<(Entity, &ObjectiveTile, &Transform), Without<TrafficTruck>>,
+    wm: Res<WorldMap>,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+) {
+    let Some(goal) = deliveries.throughput else {
+        return;
+    };
+    if deliveries.complete() || limits.defeated {
+        traffic.sim.trucks.clear();
+        for (entity, _, _) in &trucks {
+            cmds.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    let stops = |depot: bool| -> Vec<Stop> {
+        objectives
+            .iter()
+            .filter(|(_, ot, _)| ot.is_depot() == depot)
+            .filter(|(_, ot, _)| depot || deliveries.connected.contains(&ot.position()))
+            .map(|(_, ot, _)| (ot.position(), ot.goods()))
+            .collect()
+    };
+    let (depots, houses) = (stops(true), stops(false));
+    let arrived = traffic
+        .sim
+        .step(&wm.board, &depots, &houses, time.delta_seconds());
+    for truck in arrived {
+        let house = truck.route.last().copied();
+        if let Some((entity, _, tr)) = objectives
+            .iter()
+            .find(|(_, ot, _)| Some(ot.position()) == house)
+        {
+            cmds.entity(entity).insert(pulse(tr));
+        }
+    }
+
+    let mut shown = HashSet::new();
+    for (entity, truck, mut tr) in &mut trucks {
+        match traffic.sim.trucks.iter().find(|t| t.id == truck.id) {
+            Some(t) => {
+                steer(&mut tr, &t.route, truck.start, t.driven);
+                shown.insert(t.id);
+            }
+            None => cmds.entity(entity).despawn_recursive(),
+        }
+    }
+    for t in traffic.sim.trucks.iter().filter(|t| !shown.contains(&t.id)) {
+        // Trucks leave from the distribution center building
+        let start = objectives
+            .iter()
+            .find(|(_, ot, _)| ot.position() == t.route[0])
+            .map_or(
+                Vec2::new(t.route[0].0 as f32, t.route[0].1 as f32),
+                |(_, _, tr)| tr.translation.truncate(),
+            );
+        cmds.spawn((
+            TrafficTruck { id: t.id, start },
+            truck_sprite(start, t.goods, &asset_server),
+        ));
+    }
+
+    if deliveries.parcels != traffic.sim.delivered {
+        deliveries.parcels = traffic.sim.delivered;
+        if deliveries.complete() {
+            check.send(CheckObjectives);
+            return;
+        }
+    }
+    if traffic.sim.seconds >= goal.seconds && limits.failed.is_none() {
+        limits.failed = Some("Out of time");
+        check.send(CheckObjectives);
+    }
+}
+
+/// Tint the road tiles by how congested they are
+fn draw_heatmap(
+    mut traffic: ResMut<Traffic>,
+    mut tiles: Query<&mut Sprite, With<Cell>>,
+    wm: Res<WorldMap>,
+    time: Res<Time>,
+) {
+    let load = traffic.sim.load();
+    let blend = (HEAT_RATE * time.delta_seconds()).min(1.);
+    let Traffic { sim, heat, .. } = &mut *traffic;
+    heat.retain(|(x, y), _| wm.board.get(*x, *y).is_some_and(|t| t.placed));
+    for ((x, y), _) in wm.board.cells().filter(|(_, t)| t.placed) {
+        let target = sim.congestion(load.get(&(*x, *y)).copied().unwrap_or(0));
+        let heat = heat.entry((*x, *y)).or_default();
+        *heat += (target - *heat) * blend;
+        if let Some(mut sprite) = wm.get_tile(*x, *y).and_then(|e| tiles.get_mut(e).ok()) {
+            sprite.color = heat_color(*heat);
+        }
+    }
+}
+
+/// White while a road has room, orange when it is full, and red when it is jammed
+fn heat_color(heat: f32) -> Color {
+    let (from, to, t) = if heat <= 1. {
+        (Color::WHITE, colors::orange(), heat.max(0.))
+    } else {
+        (colors::orange(), colors::red(), (heat - 1.).min(1.))
+    };
+    let (from, to) = (from.as_rgba_f32(), to.as_rgba_f32());
+    Color::rgb(
+        from[0] + (to[0] - from[0]) * t,
+        from[1] + (to[1] - from[1]) * t,
+        from[2] + (to[2] - from[2]) * t,
+    )
+}
@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_easings::*;
+use trucking_core::level::Goods;
+use trucking_core::score::TRUCK_SPEED;
+
+use crate::objective::{goods_color, Deliveries, ObjectiveTile, Victory};
+use crate::score::Score;
+use crate::state::GameState;
+use crate::world::WorldMap;
+
+/// Trucks drive above the roads and below the buildings
+const TRUCK_Z: f32 = 0.5;
+
+pub struct TruckPlugin;
+
+impl Plugin for TruckPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Delivered>().add_systems(
+            (start_trucks, drive_trucks.after(start_trucks)).in_set(OnUpdate(GameState::InGame)),
+        );
+    }
+}
+
+/// Every parcel has arrived after the level was completed
+pub struct Delivered;
+
+/// A truck driving a parcel from a distribution center to a house
+#[derive(Component)]
+pub struct Truck {
+    route: Vec<(i32, i32)>,
+    /// Where the truck leaves the distribution center
+    start: Vec2,
+    /// Tiles driven so far
+    driven: f32,
+    house: Entity,
+}
+
+impl Truck {
+    fn length(&self) -> f32 {
+        (self.route.len() - 1) as f32
+    }
+}
+
+/// Where a truck is after driving `driven` tiles from `start`, passing through
+/// the middle of the tile sides and turning along the road curves
+fn road_position(route: &[(i32, i32)], start: Vec2, driven: f32) -> Vec2 {
+    let i = ((driven + 0.5) as usize).min(route.len() - 1);
+    // From the side the truck enters at zero to the side it leaves at one
+    let t = driven + 0.5 - i as f32;
+    let centre = cell(route[i]);
+    let entry = i.checked_sub(1).map(|p| (centre + cell(route[p])) / 2.);
+    let exit = route.get(i + 1).map(|n| (centre + cell(*n)) / 2.);
+    match (entry, exit) {
+        (None, Some(exit)) => start.lerp(exit, (t - 0.5) * 2.),
+        (Some(entry), None) => entry.lerp(centre, t * 2.),
+        (Some(entry), Some(exit)) => {
+            // A curve is a quarter circle around the corner between the sides
+            let corner = entry + exit - centre;
+            if corner.distance(centre) < 0.01 {
+                return entry.lerp(exit, t);
+            }
+            let from = entry - corner;
+            corner + Vec2::from_angle(from.angle_between(exit - corner) * t).rotate(from)
+        }
+        (None, None) => start,
+    }
+}
+
+/// Move a truck sprite along its route and turn it to face the road ahead
+pub fn steer(tr: &mut Transform, route: &[(i32, i32)], start: Vec2, driven: f32) {
+    let length = (route.len() - 1) as f32;
+    let position = road_position(route, start, driven);
+    let heading = road_position(route, start, (driven + 0.05).min(length)) - position;
+    tr.translation = position.extend(TRUCK_Z);
+    if heading != Vec2::ZERO {
+        tr.rotation = Quat::from_rotation_z(heading.y.atan2(heading.x));
+    }
+}
+
+pub fn truck_sprite(
+    start: Vec2,
+    goods: Option<Goods>,
+    asset_server: &Res<AssetServer>,
+) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color: goods_color(goods),
+            custom_size: Some(Vec2::splat(0.5)),
+            ..default()
+        },
+        transform: Transform::from_translation(start.extend(TRUCK_Z)),
+        texture: asset_server.load("truck.png"),
+        ..default()
+    }
+}
+
+fn cell((x, y): (i32, i32)) -> Vec2 {
+    Vec2::new(x as f32, y as f32)
+}
+
+/// Send a truck from the closest distribution center to every connected house
+pub fn start_trucks(
+    mut cmds: Commands,
+    mut event: EventReader<Victory>,
+    mut score: ResMut<Score>,
+    wm: Res<WorldMap>,
+    deliveries: Res<Deliveries>,
+    objectives: Query<(Entity, &ObjectiveTile, &Transform)>,
+    asset_server: Res<AssetServer>,
+) {
+    if event.is_empty() {
+        return;
+    }
+    event.clear();
+    let mut routes = vec![];
+    for (house, ot, _) in objectives.iter().filter(|(_, ot, _)| !ot.is_depot()) {
+        if !deliveries.connected.contains(&ot.position()) {
+            continue;
+        }
+        let closest = objectives
+            .iter()
+            .filter(|(_, depot, _)| depot.is_depot() && ot.takes(depot.goods()))
+            .filter_map(|(_, depot, tr)| {
+                wm.board
+                    .route(depot.position(), ot.position())
+                    .map(|route| (route, depot.goods(), tr.translation.truncate()))
+            })
+            .min_by_key(|(route, _, _)| route.len());
+        let Some((route, goods, start)) = closest else {
+            continue;
+        };
+        routes.push(route.len() - 1);
+        cmds.spawn((
+            Truck {
+                route,
+                start,
+                driven: 0.,
+                house,
+            },
+            truck_sprite(start, goods, &asset_server),
+        ));
+    }
+    score.count_routes(routes);
+}
+
+/// Move the trucks along their routes and let the houses know when a parcel arrives
+pub fn drive_trucks(
+    mut cmds: Commands,
+    mut trucks: Query<(Entity, &mut Truck, &mut Transform)>,
+    houses: Query<&Transform, Without<Truck>>,
+    mut delivered: EventWriter<Delivered>,
+    time: Res<Time>,
+) {
+    let mut driving = trucks.iter().len();
+    let mut arrived = false;
+    for (entity, mut truck, mut tr) in &mut trucks {
+        truck.driven += TRUCK_SPEED * time.delta_seconds();
+        if truck.driven >= truck.length() {
+            cmds.entity(entity).despawn_recursive();
+            if let Ok(house) = houses.get(truck.house) {
+                cmds.entity(truck.house).insert(pulse(house));
+            }
+            driving -= 1;
+            arrived = true;
+            continue;
+        }
+        steer(&mut tr, &truck.route, truck.start, truck.driven);
+    }
+    if arrived && driving == 0 {
+        delivered.send(Delivered);
+    }
+}
+
+/// Make a house bounce when a parcel arrives
+pub fn pulse(tr: &Transform) -> EasingChainComponent<Transform> {
+    tr.ease_to(
+        tr.with_scale(Vec3::splat(1.3)),
+        EaseFunction::QuadraticOut,
+        EasingType::Once {
+            duration: Duration::from_millis(150),
+        },
+    )
+    .ease_to(
+        *tr,
+        EaseFunction::QuadraticIn,
+        EasingType::Once {
+            duration: Duration::from_millis(250),
+        },
+    )
+}
@@ -10,6 +10,7 @@ impl Plugin for UiPlugin {
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn button_system(
     mut interaction_query: Query<
         (&Interaction, &mut BackgroundColor, &Children),
@@ -62,6 +63,17 @@ pub fn button_text(text: &str, font: Handle<Font>) -> TextBundle {
     )
 }
 
+pub fn small_text(text: &str, font: Handle<Font>) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font,
+            font_size: 20.,
+            color: colors::dark(),
+        },
+    )
+}
+
 pub fn button_image() -> ButtonBundle {
     ButtonBundle {
         style: Style {
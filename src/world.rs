@@ -1,6 +1,7 @@
-use crate::tile::{self, Border, SelectTile, SelectTileBundle, Tile, TileServer};
+use crate::tile::{self, Cell, SelectTile, SelectTileBundle, Tile, TileServer};
 use bevy::prelude::*;
-use bevy::utils::HashMap;
+use bevy::utils::{HashMap, HashSet};
+use trucking_core::{Board, Terrain};
 
 pub struct WorldPlugin;
 
@@ -8,8 +9,11 @@ impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<WorldMap>()
             .add_event::<PlaceTile>()
-            .add_system(handle_open_tiles)
-            .add_system(place_tile);
+            .add_event::<UnplaceTile>()
+            .add_system(place_tile)
+            .add_system(unplace_tile)
+            .add_system(draw_terrain.after(place_tile).after(unplace_tile))
+            .add_system(handle_open_tiles.after(place_tile).after(unplace_tile));
     }
 }
 
@@ -49,140 +53,136 @@ impl PlaceTile {
     }
 }
 
+/// Take back a placed tile
+pub struct UnplaceTile {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// The terrain of a cell, only grass cells are missing this
+#[derive(Component, Clone, Copy)]
+pub struct CellTerrain(pub Terrain);
+
 #[derive(Default, Resource)]
 pub struct WorldMap {
+    pub board: Board,
     map: HashMap<(i32, i32), Entity>,
 }
 
 impl WorldMap {
     pub fn create_tile(&mut self, x: i32, y: i32, cmds: &mut Commands) -> Entity {
         let entity = cmds.spawn(tile::TileBundle::new(x, y)).id();
-        self.map
-            .insert((x, y), entity)
-            .map_or((), |e| cmds.entity(e).despawn());
+        self.board.add_cell(x, y);
+        if let Some(e) = self.map.insert((x, y), entity) {
+            cmds.entity(e).despawn_recursive();
+        }
         entity
     }
 
     pub fn remove_tile(&mut self, x: i32, y: i32, cmds: &mut Commands) -> bool {
+        self.board.remove_cell(x, y);
         self.map
             .remove(&(x, y))
-            .map(|e| cmds.entity(e).despawn())
+            .map(|e| cmds.entity(e).despawn_recursive())
             .is_some()
     }
 
-    #[allow(unused)]
-    pub fn get_tile(&self, x: i32, y: i32) -> Option<Entity> {
-        self.map.get(&(x, y)).copied()
+    pub fn set_terrain(&mut self, x: i32, y: i32, terrain: Terrain, cmds: &mut Commands) {
+        self.board.set_terrain(x, y, terrain);
+        if let Some(e) = self.get_tile(x, y) {
+            cmds.entity(e).insert(CellTerrain(terrain));
+        }
     }
 
-    pub fn apply_borders(wm: Res<WorldMap>, mut query: Query<&mut Tile>) {
-        for ((x, y), e) in wm.map.iter() {
-            let mut tile = Tile::default();
-            if !wm.map.contains_key(&(*x, *y + 1)) {
-                tile.top = Border::Empty;
-            }
-            if !wm.map.contains_key(&(*x + 1, *y)) {
-                tile.right = Border::Empty;
-            }
-            if !wm.map.contains_key(&(*x, *y - 1)) {
-                tile.bottom = Border::Empty;
-            }
-            if !wm.map.contains_key(&(*x - 1, *y)) {
-                tile.left = Border::Empty;
-            }
-            let mut t2 = query.get_mut(*e).expect("Could not find tile entity");
-            *t2 = tile;
-        }
+    pub fn get_tile(&self, x: i32, y: i32) -> Option<Entity> {
+        self.map.get(&(x, y)).copied()
     }
 }
 
-fn place_tile(
+pub fn place_tile(
     mut event: EventReader<PlaceTile>,
-    wm: Res<WorldMap>,
+    mut wm: ResMut<WorldMap>,
     ts: Res<TileServer>,
-    mut query: Query<(&mut Tile, &mut Sprite, &mut Transform, &mut Handle<Image>)>,
+    mut query: Query<(&mut Sprite, &mut Transform, &mut Handle<Image>)>,
 ) {
     for ev in event.iter() {
-        let entity = wm.map.get(&(ev.x, ev.y));
+        let entity = wm.get_tile(ev.x, ev.y);
         if ev.silent && entity.is_none() {
             continue;
         }
         let entity = entity.expect("Tile does not exist");
-        let (mut t, mut s, mut tr, mut h) =
-            query.get_mut(*entity).expect("Could not find tile entity");
-        assert!(
-            ev.tile.placeable(&t),
-            "Could not place tile x={} y={} tile={:?} t={:?}",
-            ev.x,
-            ev.y,
-            ev.tile,
-            &t
-        );
+        if let Err(e) = wm.board.place(ev.x, ev.y, ev.tile.clone(), !ev.silent) {
+            panic!(
+                "Could not place tile x={} y={} tile={:?}: {}",
+                ev.x, ev.y, ev.tile, e
+            );
+        }
+        let (mut s, mut tr, mut h) = query.get_mut(entity).expect("Could not find tile entity");
         let (img, rot) = ts.find_texture(&ev.tile);
-        *t = ev.tile.clone();
-        t.placed = true;
         *h = img;
         tr.rotate_z(rot);
         s.color = Color::WHITE;
     }
 }
 
+pub fn unplace_tile(
+    mut event: EventReader<UnplaceTile>,
+    mut wm: ResMut<WorldMap>,
+    ts: Res<TileServer>,
+    mut query: Query<(&mut Sprite, &mut Transform, &mut Handle<Image>)>,
+) {
+    for ev in event.iter() {
+        if let Err(e) = wm.board.unplace(ev.x, ev.y) {
+            panic!("Could not remove tile x={} y={}: {}", ev.x, ev.y, e);
+        }
+        let entity = wm.get_tile(ev.x, ev.y).expect("Tile does not exist");
+        let (mut s, mut tr, mut h) = query.get_mut(entity).expect("Could not find tile entity");
+        (*h, s.color) = ts.terrain_texture(wm.board.terrain(ev.x, ev.y));
+        tr.rotation = Quat::IDENTITY;
+    }
+}
+
+fn draw_terrain(
+    wm: Res<WorldMap>,
+    ts: Res<TileServer>,
+    mut query: Query<(&Cell, &CellTerrain, &mut Sprite, &mut Handle<Image>), Changed<CellTerrain>>,
+) {
+    for (cell, terrain, mut s, mut h) in query.iter_mut() {
+        if wm.board.get(cell.x, cell.y).is_some_and(|t| !t.placed) {
+            (*h, s.color) = ts.terrain_texture(terrain.0);
+        }
+    }
+}
+
 fn handle_open_tiles(
-    mut event: EventReader<PlaceTile>,
+    event: EventReader<PlaceTile>,
+    removed: EventReader<UnplaceTile>,
     wm: Res<WorldMap>,
     mut cmds: Commands,
-    mut query: Query<&mut Tile>,
+    cells: Query<&Cell>,
     sel_query: Query<(&Parent, Entity), With<SelectTile>>,
     asset_server: Res<AssetServer>,
 ) {
-    for ev in event.iter() {
-        let (x, y, tile) = (ev.x, ev.y, &ev.tile);
-        let entity = wm.map.get(&(ev.x, ev.y));
-        if ev.silent && entity.is_none() {
-            continue;
-        }
-        let entity = entity.expect("Tile does not exist");
-        if let Some(e) = wm.map.get(&(x + 1, y)) {
-            let mut t = query.get_mut(*e).expect("Could not find entity");
-            t.left = tile.right;
-            if !ev.silent && !t.placed && tile.right == Border::Road {
-                cmds.entity(*e).with_children(|p| {
-                    p.spawn(SelectTileBundle::new(&asset_server));
-                });
-            }
-        }
-        if let Some(e) = wm.map.get(&(x - 1, y)) {
-            let mut t = query.get_mut(*e).expect("Could not find entity");
-            t.right = tile.left;
-            if !ev.silent && !t.placed && tile.left == Border::Road {
-                cmds.entity(*e).with_children(|p| {
-                    p.spawn(SelectTileBundle::new(&asset_server));
-                });
-            }
-        }
-        if let Some(e) = wm.map.get(&(x, y + 1)) {
-            let mut t = query.get_mut(*e).expect("Could not find entity");
-            t.bottom = tile.top;
-            if !ev.silent && !t.placed && tile.top == Border::Road {
-                cmds.entity(*e).with_children(|p| {
-                    p.spawn(SelectTileBundle::new(&asset_server));
-                });
-            }
+    if event.is_empty() && removed.is_empty() {
+        return;
+    }
+    let mut marked = HashSet::new();
+    for (p, e) in &sel_query {
+        let cell = cells.get(p.get()).expect("Could not find entity");
+        if wm.board.is_open(cell.x, cell.y) {
+            marked.insert((cell.x, cell.y));
+        } else {
+            cmds.entity(p.get()).remove_children(&[e]);
+            cmds.entity(e).despawn();
         }
-        if let Some(e) = wm.map.get(&(x, y - 1)) {
-            let mut t = query.get_mut(*e).expect("Could not find entity");
-            t.top = tile.bottom;
-            if !ev.silent && !t.placed && tile.bottom == Border::Road {
-                cmds.entity(*e).with_children(|p| {
+    }
+    for (x, y) in wm.board.open_slots() {
+        if !marked.contains(&(*x, *y)) {
+            if let Some(e) = wm.get_tile(*x, *y) {
+                cmds.entity(e).with_children(|p| {
                     p.spawn(SelectTileBundle::new(&asset_server));
                 });
             }
         }
-        for (p, e) in &sel_query {
-            if p.get() == *entity {
-                cmds.entity(*entity).remove_children(&[e]);
-                cmds.entity(e).despawn();
-            }
-        }
     }
 }
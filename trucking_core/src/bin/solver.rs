@@ -0,0 +1,78 @@
+use std::process::ExitCode;
+
+use trucking_core::solver::SolveError;
+use trucking_core::{LevelData, Solver};
+
+const USAGE: &str = "Usage: solver <level file> [max nodes]";
+const MAX_NODES: usize = 2_000_000;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (path, limit) = match args.as_slice() {
+        [path] => (path, MAX_NODES),
+        [path, limit] => match limit.parse() {
+            Ok(limit) => (path, limit),
+            Err(_) => {
+                eprintln!("{}", USAGE);
+                return ExitCode::FAILURE;
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let level = match std::fs::read_to_string(path) {
+        Ok(text) => match LevelData::parse(&text) {
+            Ok(level) => level,
+            Err(e) => {
+                eprintln!("Could not parse {}: {}", path, e);
+                return ExitCode::FAILURE;
+            }
+        },
+        Err(e) => {
+            eprintln!("Could not read {}: {}", path, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut solver = Solver::from_level(&level).with_node_limit(limit);
+    let (result, shortest) = match solver.solve() {
+        Err(SolveError::NodeLimit) => {
+            eprintln!(
+                "{}: no shortest solution found in {} nodes, looking for any solution",
+                level.name,
+                solver.nodes()
+            );
+            (solver.solve_any(), false)
+        }
+        result => (result, true),
+    };
+    match result {
+        Ok(Some(solution)) => {
+            println!(
+                "{}: solved with {} tiles and {} skips{} ({} nodes)",
+                level.name,
+                solution.tiles,
+                solution.skips,
+                if shortest { "" } else { ", not the shortest" },
+                solver.nodes()
+            );
+            for (i, m) in solution.moves.iter().enumerate() {
+                println!("{:>4}. {}", i + 1, m);
+            }
+            ExitCode::SUCCESS
+        }
+        Ok(None) => {
+            println!(
+                "{}: cannot be won with the {} tiles in the pile",
+                level.name,
+                level.deck().pile().len()
+            );
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("{}: {} ({} nodes)", level.name, e, solver.nodes());
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,402 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt;
+
+use crate::terrain::Terrain;
+use crate::tile::{Border, Side, Tile};
+
+/// The cells of a level and their terrain, which tiles have been placed on them, and which
+/// cells are open for placing the next tile.
+#[derive(Default, Debug, Clone)]
+pub struct Board {
+    cells: HashMap<(i32, i32), Tile>,
+    open: HashSet<(i32, i32)>,
+    silent: HashSet<(i32, i32)>,
+    terrain: HashMap<(i32, i32), Terrain>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PlaceError {
+    NoCell,
+    NotPlaceable,
+    NotPlaced,
+}
+
+impl fmt::Display for PlaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlaceError::NoCell => write!(f, "there is no cell at that position"),
+            PlaceError::NotPlaceable => write!(f, "the tile does not fit the cell"),
+            PlaceError::NotPlaced => write!(f, "there is no tile placed on the cell"),
+        }
+    }
+}
+
+impl std::error::Error for PlaceError {}
+
+impl Board {
+    /// Returns true if there already was a cell at the position
+    pub fn add_cell(&mut self, x: i32, y: i32) -> bool {
+        self.open.remove(&(x, y));
+        self.silent.remove(&(x, y));
+        self.cells.insert((x, y), Tile::default()).is_some()
+    }
+
+    pub fn remove_cell(&mut self, x: i32, y: i32) -> bool {
+        self.open.remove(&(x, y));
+        self.silent.remove(&(x, y));
+        self.terrain.remove(&(x, y));
+        self.cells.remove(&(x, y)).is_some()
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        self.cells.contains_key(&(x, y))
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<&Tile> {
+        self.cells.get(&(x, y))
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = (&(i32, i32), &Tile)> {
+        self.cells.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn terrain(&self, x: i32, y: i32) -> Terrain {
+        self.terrain.get(&(x, y)).copied().unwrap_or_default()
+    }
+
+    pub fn set_terrain(&mut self, x: i32, y: i32, terrain: Terrain) {
+        if terrain == Terrain::Grass {
+            self.terrain.remove(&(x, y));
+        } else {
+            self.terrain.insert((x, y), terrain);
+        }
+    }
+
+    /// Cells that are connected to the road network and can receive a tile
+    pub fn open_slots(&self) -> impl Iterator<Item = &(i32, i32)> {
+        self.open.iter()
+    }
+
+    pub fn is_open(&self, x: i32, y: i32) -> bool {
+        self.open.contains(&(x, y))
+    }
+
+    /// Block the borders of cells that lie on the edge of the board
+    pub fn apply_borders(&mut self) {
+        let keys: Vec<(i32, i32)> = self.cells.keys().copied().collect();
+        for (x, y) in keys {
+            let mut tile = Tile::default();
+            if !self.contains(x, y + 1) {
+                tile.top = Border::Empty;
+            }
+            if !self.contains(x + 1, y) {
+                tile.right = Border::Empty;
+            }
+            if !self.contains(x, y - 1) {
+                tile.bottom = Border::Empty;
+            }
+            if !self.contains(x - 1, y) {
+                tile.left = Border::Empty;
+            }
+            self.cells.insert((x, y), tile);
+        }
+    }
+
+    pub fn can_place(&self, x: i32, y: i32, tile: &Tile) -> bool {
+        self.get(x, y).is_some_and(|t| tile.placeable(t)) && self.terrain(x, y).accepts(tile)
+    }
+
+    /// Whether the tile can be placed in any open slot, in any rotation
+    pub fn fits_anywhere(&self, tile: &Tile) -> bool {
+        let rotations = [
+            tile.clone(),
+            tile.rotate90(),
+            tile.rotate180(),
+            tile.rotate270(),
+        ];
+        self.open_slots()
+            .any(|(x, y)| rotations.iter().any(|t| self.can_place(*x, *y, t)))
+    }
+
+    /// Place a tile and constrain the borders of the neighbouring cells.
+    /// If `open` is set then the roads of the tile will open new slots.
+    pub fn place(&mut self, x: i32, y: i32, tile: Tile, open: bool) -> Result<(), PlaceError> {
+        let terrain = self.terrain(x, y);
+        let cell = self.cells.get_mut(&(x, y)).ok_or(PlaceError::NoCell)?;
+        if !tile.placeable(cell) || !terrain.accepts(&tile) {
+            return Err(PlaceError::NotPlaceable);
+        }
+        *cell = tile.clone();
+        cell.placed = true;
+        self.open.remove(&(x, y));
+        if !open {
+            self.silent.insert((x, y));
+        }
+        for side in Side::ALL {
+            let pos = side.step((x, y));
+            if let Some(t) = self.cells.get_mut(&pos) {
+                let border = tile.border(side);
+                *t.border_mut(side.opposite()) = border;
+                if open && !t.placed && border == Border::Road {
+                    self.open.insert(pos);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove a placed tile, returning the borders of the cell and of its
+    /// neighbours to how they were before it was placed
+    pub fn unplace(&mut self, x: i32, y: i32) -> Result<Tile, PlaceError> {
+        let tile = match self.cells.get(&(x, y)) {
+            None => return Err(PlaceError::NoCell),
+            Some(t) if !t.placed => return Err(PlaceError::NotPlaced),
+            Some(t) => t.clone(),
+        };
+        self.silent.remove(&(x, y));
+        let mut cell = Tile::default();
+        for side in Side::ALL {
+            let pos = side.step((x, y));
+            *cell.border_mut(side) = match self.cells.get_mut(&pos) {
+                None => Border::Empty,
+                Some(t) if t.placed => t.border(side.opposite()),
+                Some(t) => {
+                    *t.border_mut(side.opposite()) = Border::Any;
+                    Border::Any
+                }
+            };
+        }
+        self.cells.insert((x, y), cell);
+        self.update_open((x, y));
+        for side in Side::ALL {
+            self.update_open(side.step((x, y)));
+        }
+        Ok(tile)
+    }
+
+    fn update_open(&mut self, pos: (i32, i32)) {
+        let open = self.cells.get(&pos).is_some_and(|t| !t.placed)
+            && Side::ALL.into_iter().any(|side| {
+                let n = side.step(pos);
+                !self.silent.contains(&n)
+                    && self
+                        .cells
+                        .get(&n)
+                        .is_some_and(|t| t.placed && t.border(side.opposite()) == Border::Road)
+            });
+        if open {
+            self.open.insert(pos);
+        } else {
+            self.open.remove(&pos);
+        }
+    }
+
+    /// Find all placed tiles that are connected by roads to the tile at `start`
+    pub fn reachable(&self, start: (i32, i32)) -> HashSet<(i32, i32)> {
+        let mut found = HashSet::new();
+        // Roads are followed once in each direction, since a bridge can be
+        // crossed on two roads that do not connect
+        let mut followed = HashSet::new();
+        let mut stack = vec![(start, None)];
+        while let Some((pos, from)) = stack.pop() {
+            let tile = match self.cells.get(&pos) {
+                Some(t) if t.placed => t,
+                _ => continue,
+            };
+            found.insert(pos);
+            for side in tile.exits(from) {
+                if followed.insert((pos, side)) {
+                    stack.push((side.step(pos), Some(side.opposite())));
+                }
+            }
+        }
+        found
+    }
+
+    /// The shortest road from the tile at `start` to the tile at `goal`, as the
+    /// positions of the tiles along the way
+    pub fn route(&self, start: (i32, i32), goal: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        self.cheapest_route(start, goal, |_| 1)
+            .map(|(_, route)| route)
+    }
+
+    /// The road from `start` to `goal` with the lowest total `cost` of the
+    /// tiles entered along the way, and that cost
+    pub fn cheapest_route(
+        &self,
+        start: (i32, i32),
+        goal: (i32, i32),
+        cost: impl Fn((i32, i32)) -> usize,
+    ) -> Option<(usize, Vec<(i32, i32)>)> {
+        // Only bridges care about the side a road enters from
+        let key = |pos, from: Option<Side>| match self.cells.get(&pos) {
+            Some(t) if t.bridge => (pos, from),
+            _ => (pos, None),
+        };
+        // Where each position and entry side was reached from, the start leads to itself
+        let first = key(start, None);
+        let mut previous = HashMap::from([(first, first)]);
+        let mut best = HashMap::from([(first, 0)]);
+        let mut queue = BinaryHeap::from([Reverse((0, start, None))]);
+        while let Some(Reverse((total, pos, from))) = queue.pop() {
+            if best.get(&key(pos, from)).is_some_and(|b| *b < total) {
+                continue;
+            }
+            if pos == goal {
+                let mut route = vec![pos];
+                let mut current = key(pos, from);
+                while current != first {
+                    current = previous[&current];
+                    route.push(current.0);
+                }
+                route.reverse();
+                return Some((total, route));
+            }
+            let tile = match self.cells.get(&pos) {
+                Some(t) if t.placed => t,
+                _ => continue,
+            };
+            for side in tile.exits(from) {
+                let (next, entry) = (side.step(pos), Some(side.opposite()));
+                let next_total = total + cost(next);
+                if best.get(&key(next, entry)).is_none_or(|b| next_total < *b) {
+                    best.insert(key(next, entry), next_total);
+                    previous.insert(key(next, entry), key(pos, from));
+                    queue.push(Reverse((next_total, next, entry)));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A row of cells from (0, 0) to (len - 1, 0)
+    fn row(len: i32) -> Board {
+        let mut board = Board::default();
+        for x in 0..len {
+            board.add_cell(x, 0);
+        }
+        board.apply_borders();
+        board
+    }
+
+    #[test]
+    fn place_constrains_neighbours_and_opens_slots() {
+        let mut board = row(3);
+        assert_eq!(
+            board.place(0, 0, Tile::create("t"), true),
+            Err(PlaceError::NotPlaceable)
+        );
+        assert_eq!(
+            board.place(5, 0, Tile::create("r"), true),
+            Err(PlaceError::NoCell)
+        );
+        board.place(0, 0, Tile::create("r"), true).unwrap();
+        assert!(board.is_open(1, 0));
+        assert!(!board.is_open(0, 0));
+        assert!(!board.can_place(1, 0, &Tile::create("r")));
+        assert!(board.can_place(1, 0, &Tile::create("rl")));
+        assert_eq!(
+            board.place(0, 0, Tile::create("r"), true),
+            Err(PlaceError::NotPlaceable)
+        );
+    }
+
+    #[test]
+    fn silent_tiles_do_not_open_slots() {
+        let mut board = row(2);
+        board.place(0, 0, Tile::create("r"), false).unwrap();
+        assert_eq!(board.open_slots().count(), 0);
+        assert!(board.can_place(1, 0, &Tile::create("l")));
+    }
+
+    #[test]
+    fn unplace_restores_the_board() {
+        let mut board = row(3);
+        board.place(0, 0, Tile::create("r"), true).unwrap();
+        board.place(1, 0, Tile::create("rl"), true).unwrap();
+        assert!(board.is_open(2, 0));
+        assert_eq!(
+            board.unplace(1, 0),
+            Ok(Tile {
+                placed: true,
+                ..Tile::create("rl")
+            })
+        );
+        assert!(board.is_open(1, 0));
+        assert!(!board.is_open(2, 0));
+        assert_eq!(board.get(2, 0).unwrap().left, Border::Any);
+        assert!(board.can_place(1, 0, &Tile::create("rl")));
+        assert!(!board.can_place(1, 0, &Tile::create("t")));
+        assert_eq!(board.unplace(1, 0), Err(PlaceError::NotPlaced));
+        assert_eq!(board.unplace(5, 0), Err(PlaceError::NoCell));
+    }
+
+    #[test]
+    fn reachable_follows_the_roads() {
+        let mut board = row(4);
+        board.place(0, 0, Tile::create("r"), true).unwrap();
+        board.place(1, 0, Tile::create("l"), true).unwrap();
+        board.place(3, 0, Tile::create(""), true).unwrap();
+        assert_eq!(board.reachable((0, 0)), HashSet::from([(0, 0), (1, 0)]));
+        assert_eq!(board.reachable((3, 0)), HashSet::from([(3, 0)]));
+        assert!(board.reachable((2, 0)).is_empty());
+    }
+
+    /// A plus shape of cells around (1, 1), with a crossing at the centre
+    fn crossing(centre: &str) -> Board {
+        let mut board = Board::default();
+        for (x, y) in [(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)] {
+            board.add_cell(x, y);
+        }
+        board.apply_borders();
+        board.place(1, 1, Tile::create(centre), true).unwrap();
+        board.place(0, 1, Tile::create("r"), true).unwrap();
+        board.place(2, 1, Tile::create("l"), true).unwrap();
+        board.place(1, 0, Tile::create("t"), true).unwrap();
+        board.place(1, 2, Tile::create("b"), true).unwrap();
+        board
+    }
+
+    #[test]
+    fn bridges_do_not_join_crossing_roads() {
+        let board = crossing("trbl=");
+        assert_eq!(
+            board.reachable((0, 1)),
+            HashSet::from([(0, 1), (1, 1), (2, 1)])
+        );
+        assert_eq!(
+            board.reachable((1, 0)),
+            HashSet::from([(1, 0), (1, 1), (1, 2)])
+        );
+        assert_eq!(
+            board.route((0, 1), (2, 1)),
+            Some(vec![(0, 1), (1, 1), (2, 1)])
+        );
+        assert_eq!(board.route((0, 1), (1, 2)), None);
+        assert_eq!(crossing("trbl").reachable((0, 1)).len(), 5);
+        assert_eq!(
+            crossing("trbl").route((0, 1), (1, 2)),
+            Some(vec![(0, 1), (1, 1), (1, 2)])
+        );
+    }
+
+    #[test]
+    fn bridges_are_crossed_from_the_start_tile() {
+        let board = crossing("trbl=");
+        assert_eq!(board.reachable((1, 1)).len(), 5);
+    }
+}
@@ -0,0 +1,227 @@
+use crate::generator::{self, Difficulty};
+use crate::level::LevelData;
+use crate::save::Record;
+
+/// Days are counted from 1970-01-01 in UTC, so that everyone has the same
+/// puzzle at the same time.
+pub fn day_from_unix(seconds: u64) -> i64 {
+    (seconds / 86_400) as i64
+}
+
+/// The year, month, and day of the month
+pub fn date(day: i64) -> (i64, u32, u32) {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = day + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
+
+/// The day from a year, month, and day of the month
+pub fn day(year: i64, month: u32, day: u32) -> i64 {
+    // http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+pub fn name(day: i64) -> String {
+    let (y, m, d) = date(day);
+    format!("Daily {}-{:02}-{:02}", y, m, d)
+}
+
+/// The day of a daily level from its name
+pub fn parse_name(name: &str) -> Option<i64> {
+    let date = name.strip_prefix("Daily ")?;
+    let mut parts = date.splitn(3, '-');
+    let y = parts.next()?.parse().ok()?;
+    let m = parts.next()?.parse().ok()?;
+    let d = parts.next()?.parse().ok()?;
+    let result = day(y, m, d);
+    (self::name(result) == name).then_some(result)
+}
+
+/// Easy at the start of the week and hard on the weekend
+fn difficulty(day: i64) -> Difficulty {
+    // 1970-01-01 was a Thursday
+    match (day + 3).rem_euclid(7) {
+        0 | 1 => Difficulty::Easy,
+        2..=4 => Difficulty::Normal,
+        _ => Difficulty::Hard,
+    }
+}
+
+/// The level of the day, the pile is also seeded from the date
+pub fn level(day: i64) -> LevelData {
+    let seed = (day as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    let mut level = generator::generate(seed, difficulty(day));
+    level.name = name(day);
+    level.text = Some(format!("{} challenge", difficulty(day)));
+    level
+}
+
+/// Daily levels that have been completed on consecutive days
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Streak {
+    /// The last completed day
+    pub day: i64,
+    pub length: usize,
+    pub longest: usize,
+    /// The results of the last completed day
+    pub record: Option<Record>,
+}
+
+impl Streak {
+    /// The length of the streak, a streak is broken if yesterday was skipped
+    pub fn current(&self, today: i64) -> usize {
+        if self.record.is_some() && today - self.day <= 1 {
+            self.length
+        } else {
+            0
+        }
+    }
+
+    pub fn completed(&self, today: i64) -> bool {
+        self.record.is_some() && self.day == today
+    }
+
+    /// Complete a day, playing an older day again only improves its record
+    pub fn complete(&mut self, day: i64, record: Record) -> bool {
+        let old = *self;
+        match self.record {
+            Some(best) if self.day == day => self.record = Some(best.best(&record)),
+            _ if self.record.is_some() && day < self.day => return false,
+            _ => {
+                self.length = if self.current(day) > 0 {
+                    self.length + 1
+                } else {
+                    1
+                };
+                self.longest = self.longest.max(self.length);
+                self.day = day;
+                self.record = Some(record);
+            }
+        }
+        *self != old
+    }
+}
+
+/// A text to share the result of a daily level
+pub fn summary(day: i64, record: &Record, streak: usize) -> String {
+    let stars: String = (0..3)
+        .map(|i| if i < record.stars { '*' } else { '-' })
+        .collect();
+    let points = record
+        .points
+        .map_or(String::new(), |p| format!(", {} points", p));
+    format!(
+        "Tiler's Trucking Co, {}\n[{}] {} tiles in {}:{:02}{}\nStreak: {} day{}",
+        name(day),
+        stars,
+        record.tiles,
+        record.seconds as u32 / 60,
+        record.seconds as u32 % 60,
+        points,
+        streak,
+        if streak == 1 { "" } else { "s" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(stars: usize, tiles: usize) -> Record {
+        Record {
+            stars,
+            tiles,
+            seconds: 60.,
+            points: None,
+        }
+    }
+
+    #[test]
+    fn day_and_date_are_inverse() {
+        let known = [
+            ((1970, 1, 1), 0),
+            ((1969, 12, 31), -1),
+            ((2000, 2, 29), 11_016),
+            ((2024, 2, 29), 19_782),
+            ((1900, 3, 1), -25_508),
+            ((1600, 2, 29), -135_081),
+        ];
+        for ((y, m, d), n) in known {
+            assert_eq!(day(y, m, d), n);
+            assert_eq!(date(n), (y, m, d));
+        }
+        for n in -150_000..150_000 {
+            let (y, m, d) = date(n);
+            assert_eq!(day(y, m, d), n);
+        }
+        assert_eq!(day_from_unix(86_400 * 3 - 1), 2);
+    }
+
+    #[test]
+    fn parse_name_only_reads_canonical_dates() {
+        assert_eq!(name(19_782), "Daily 2024-02-29");
+        assert_eq!(parse_name("Daily 2024-02-29"), Some(19_782));
+        assert_eq!(parse_name("Daily 2023-02-29"), None);
+        assert_eq!(parse_name("Daily 2024-2-29"), None);
+        assert_eq!(parse_name("Daily 2024-13-01"), None);
+        assert_eq!(parse_name("Daily 2024-01-32"), None);
+        assert_eq!(parse_name("Daily 2024-01"), None);
+        assert_eq!(parse_name("Weekly 2024-01-01"), None);
+    }
+
+    #[test]
+    fn consecutive_days_grow_the_streak() {
+        let mut streak = Streak::default();
+        assert!(streak.complete(100, record(2, 10)));
+        assert!(streak.complete(101, record(1, 12)));
+        assert_eq!((streak.length, streak.longest), (2, 2));
+        assert_eq!(streak.current(102), 2);
+        assert_eq!(streak.current(103), 0);
+        assert!(streak.completed(101));
+        assert!(!streak.completed(102));
+    }
+
+    #[test]
+    fn a_skipped_day_resets_the_streak() {
+        let mut streak = Streak::default();
+        streak.complete(100, record(2, 10));
+        streak.complete(101, record(2, 10));
+        streak.complete(102, record(2, 10));
+        assert!(streak.complete(104, record(3, 8)));
+        assert_eq!((streak.day, streak.length, streak.longest), (104, 1, 3));
+    }
+
+    #[test]
+    fn playing_today_again_keeps_the_best_record() {
+        let mut streak = Streak::default();
+        streak.complete(100, record(2, 10));
+        assert!(streak.complete(100, record(3, 12)));
+        assert!(!streak.complete(100, record(1, 14)));
+        assert_eq!(streak.length, 1);
+        let best = streak.record.unwrap();
+        assert_eq!((best.stars, best.tiles), (3, 10));
+    }
+
+    #[test]
+    fn older_days_do_not_count() {
+        let mut streak = Streak::default();
+        streak.complete(100, record(2, 10));
+        assert!(!streak.complete(99, record(3, 5)));
+        assert_eq!(streak.day, 100);
+        assert_eq!(streak.record, Some(record(2, 10)));
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::VecDeque;
+
+use fastrand::Rng;
+
+use crate::tile::Tile;
+
+#[derive(Default, Debug, Clone)]
+pub struct Deck {
+    deck: Vec<(f32, Tile)>,
+    pile: VecDeque<Tile>,
+    drawn: usize,
+    skipped: usize,
+    finite: bool,
+    held: Option<Tile>,
+    hold_used: bool,
+    /// The state of the random generator for refilling the pile
+    rng: u64,
+}
+
+impl Deck {
+    pub fn add_tile(&mut self, tile: Tile, prob: f32) {
+        self.deck.push((prob, tile));
+    }
+
+    pub fn add_all_tiles(&mut self) {
+        self.deck.push((0.3, Tile::create("tr")));
+        self.deck.push((0.3, Tile::create("tb")));
+        self.deck.push((0.2, Tile::create("trb")));
+        self.deck.push((0.1, Tile::create("trbl")));
+        self.deck.push((0.1, Tile::create("t")));
+    }
+
+    /// Add random tiles to the pile, later refills continue from the same seed
+    pub fn fill_pile(&mut self, num: usize, seed: u64) {
+        let rng = Rng::with_seed(seed);
+        self.pile.reserve(num);
+        for _ in 0..num {
+            self.push_random(&rng);
+        }
+        self.rng = rng.get_seed();
+    }
+
+    fn push_random(&mut self, rng: &Rng) {
+        let tot: f32 = self.deck.iter().map(|(p, _)| p).sum();
+        let mut r = rng.f32() * tot;
+        for (p, t) in self.deck.iter() {
+            if r < *p {
+                self.pile.push_back(match rng.u8(0..4) {
+                    1 => t.rotate90(),
+                    2 => t.rotate180(),
+                    3 => t.rotate270(),
+                    _ => t.clone(),
+                });
+                break;
+            }
+            r -= p;
+        }
+    }
+
+    fn add_rnd_to_pile(&mut self) {
+        let rng = Rng::with_seed(self.rng);
+        self.push_random(&rng);
+        self.rng = rng.get_seed();
+    }
+
+    /// The random state can be stored and restored to continue the same refills
+    pub fn rng_state(&self) -> u64 {
+        self.rng
+    }
+
+    pub fn set_rng_state(&mut self, state: u64) {
+        self.rng = state;
+    }
+
+    pub fn add_to_pile(&mut self, tile: Tile) {
+        self.pile.push_back(tile);
+    }
+
+    pub fn get_top(&self) -> Option<&Tile> {
+        self.pile.front()
+    }
+
+    pub fn weights(&self) -> &[(f32, Tile)] {
+        &self.deck
+    }
+
+    pub fn pile(&self) -> &VecDeque<Tile> {
+        &self.pile
+    }
+
+    /// How many tiles have been drawn from the pile
+    pub fn drawn(&self) -> usize {
+        self.drawn
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// A finite pile is not refilled with random tiles when it runs out
+    pub fn set_finite(&mut self, finite: bool) {
+        self.finite = finite;
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.finite
+    }
+
+    /// The tile that has been put aside
+    pub fn held(&self) -> Option<&Tile> {
+        self.held.as_ref()
+    }
+
+    /// Whether a tile has been held since the last tile was drawn
+    pub fn hold_used(&self) -> bool {
+        self.hold_used
+    }
+
+    pub fn can_hold(&self) -> bool {
+        !self.hold_used && !self.pile.is_empty()
+    }
+
+    /// Put the top tile aside, or swap it with the tile that is already held.
+    /// This can be done once for every tile that is placed.
+    pub fn hold(&mut self) -> bool {
+        if !self.can_hold() {
+            return false;
+        }
+        let top = self.pile.pop_front().unwrap();
+        match self.held.replace(top) {
+            Some(tile) => self.pile.push_front(tile),
+            None if self.pile.is_empty() && !self.finite => self.add_rnd_to_pile(),
+            None => {}
+        }
+        self.hold_used = true;
+        true
+    }
+
+    pub fn rotate_top(&mut self) -> Option<&Tile> {
+        let t = self.pile.front_mut()?;
+        *t = t.rotate90();
+        Some(t)
+    }
+
+    /// Discard the top tile and return the one below it
+    pub fn draw(&mut self) -> Option<&Tile> {
+        self.pile.pop_front();
+        self.drawn += 1;
+        if self.pile.is_empty() && !self.finite {
+            self.add_rnd_to_pile();
+        }
+        self.pile.front()
+    }
+
+    /// Draw after placing the top tile, which allows holding again
+    pub fn place(&mut self) -> Option<&Tile> {
+        self.hold_used = false;
+        self.draw()
+    }
+
+    /// Put a drawn tile back on top of the pile, as if it had not been drawn
+    pub fn undraw(&mut self, tile: Tile, hold_used: bool) {
+        self.pile.push_front(tile);
+        self.drawn = self.drawn.saturating_sub(1);
+        self.hold_used = hold_used;
+    }
+
+    /// Draw without placing the top tile
+    pub fn skip(&mut self) -> Option<&Tile> {
+        self.skipped += 1;
+        self.draw()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deck() -> Deck {
+        let mut deck = Deck::default();
+        deck.add_all_tiles();
+        deck
+    }
+
+    #[test]
+    fn fill_pile_pushes_one_tile_each() {
+        let mut deck = deck();
+        deck.fill_pile(50, 7);
+        assert_eq!(deck.pile().len(), 50);
+    }
+
+    #[test]
+    fn fill_pile_is_deterministic() {
+        let (mut a, mut b) = (deck(), deck());
+        a.fill_pile(20, 42);
+        b.fill_pile(20, 42);
+        assert_eq!(a.pile(), b.pile());
+        assert_eq!(a.rng_state(), b.rng_state());
+        let mut c = deck();
+        c.fill_pile(20, 43);
+        assert_ne!(a.pile(), c.pile());
+    }
+
+    #[test]
+    fn refills_continue_from_the_seed() {
+        let (mut a, mut b) = (deck(), deck());
+        a.fill_pile(1, 5);
+        b.fill_pile(1, 5);
+        let drawn: Vec<Tile> = (0..20).filter_map(|_| a.draw().cloned()).collect();
+        assert_eq!(drawn.len(), 20);
+        for tile in drawn.iter() {
+            assert_eq!(b.draw(), Some(tile));
+        }
+    }
+
+    #[test]
+    fn restored_rng_state_repeats_the_refills() {
+        let mut a = deck();
+        a.fill_pile(3, 9);
+        a.draw();
+        let mut b = deck();
+        for tile in a.pile() {
+            b.add_to_pile(tile.clone());
+        }
+        b.set_rng_state(a.rng_state());
+        let next: Vec<Tile> = (0..10).filter_map(|_| a.draw().cloned()).collect();
+        let again: Vec<Tile> = (0..10).filter_map(|_| b.draw().cloned()).collect();
+        assert_eq!(next, again);
+    }
+
+    #[test]
+    fn draw_takes_the_top_tile() {
+        let mut deck = deck();
+        deck.add_to_pile(Tile::create("t"));
+        deck.add_to_pile(Tile::create("tb"));
+        assert_eq!(deck.get_top(), Some(&Tile::create("t")));
+        assert_eq!(deck.draw(), Some(&Tile::create("tb")));
+        assert_eq!(deck.drawn(), 1);
+    }
+
+    #[test]
+    fn hold_once_per_placement() {
+        let mut deck = deck();
+        deck.fill_pile(10, 1);
+        assert!(deck.hold());
+        assert!(!deck.hold());
+        deck.skip();
+        assert!(!deck.hold());
+        deck.place();
+        assert!(deck.hold());
+    }
+
+    #[test]
+    fn undraw_puts_the_tile_back() {
+        let mut deck = deck();
+        deck.add_to_pile(Tile::create("t"));
+        deck.add_to_pile(Tile::create("tb"));
+        deck.hold();
+        deck.skip();
+        deck.undraw(Tile::create("rl"), true);
+        assert_eq!(deck.get_top(), Some(&Tile::create("rl")));
+        assert_eq!(deck.drawn(), 0);
+        assert_eq!(deck.skipped(), 1);
+        assert_eq!(deck.held(), Some(&Tile::create("t")));
+        assert!(!deck.can_hold());
+    }
+
+    #[test]
+    fn draw_refills_unless_finite() {
+        let mut endless = deck();
+        endless.add_to_pile(Tile::create("t"));
+        assert!(endless.draw().is_some());
+        assert_eq!(endless.pile().len(), 1);
+
+        let mut finite = deck();
+        finite.set_finite(true);
+        finite.add_to_pile(Tile::create("t"));
+        assert_eq!(finite.draw(), None);
+        assert!(finite.pile().is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+use fastrand::Rng;
+
+use crate::board::Board;
+use crate::deck::Deck;
+use crate::level::LevelData;
+use crate::tile::{Border, Side, Tile};
+
+/// How many cells the board reaches past every placed tile
+pub const MARGIN: i32 = 3;
+/// How far from the placed tiles new houses appear
+const HOUSE_DISTANCE: (i32, i32) = (3, 5);
+/// Turns between houses, the interval shrinks as more houses appear
+const INTERVAL: (usize, usize) = (4, 8);
+/// Turns a house waits on top of two turns per cell of distance
+const PATIENCE: usize = 6;
+
+/// A level that starts with a depot on a small board
+pub fn level(seed: u64) -> LevelData {
+    let rng = Rng::with_seed(seed);
+    let mut deck = Deck::default();
+    deck.add_all_tiles();
+    let depot = [
+        Tile::create("trb"),
+        Tile::create("tb"),
+        Tile::create("trbl"),
+    ][rng.usize(..3)]
+    .clone();
+    LevelData {
+        name: format!("Endless #{}", seed),
+        text: Some("Connect the houses before they give up waiting".to_string()),
+        camera: Some((0., 0.)),
+        cells: (-MARGIN..=MARGIN)
+            .flat_map(|x| (-MARGIN..=MARGIN).map(move |y| (x, y)))
+            .collect(),
+        depots: vec![(0, 0, depot)],
+        tiles: deck.weights().to_vec(),
+        fill: Some((100, rng.u64(..))),
+        undo_limit: Some(0),
+        hint_limit: Some(0),
+        preview: 2,
+        endless: true,
+        ..Default::default()
+    }
+}
+
+/// A house that has to be connected within `patience` turns
+#[derive(Debug, Clone, PartialEq)]
+pub struct House {
+    pub x: i32,
+    pub y: i32,
+    pub tile: Tile,
+    pub patience: usize,
+}
+
+/// When and where new houses appear in an endless level
+#[derive(Debug, Default, Clone)]
+pub struct Spawner {
+    rng: u64,
+    next: usize,
+    houses: usize,
+}
+
+impl Spawner {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: seed,
+            ..Default::default()
+        }
+    }
+
+    /// The turn when the next house appears
+    pub fn next_turn(&self) -> usize {
+        self.next
+    }
+
+    /// A new house if it is time for one and there is room for it
+    pub fn spawn(&mut self, board: &Board, turn: usize) -> Option<House> {
+        if turn < self.next {
+            return None;
+        }
+        let rng = Rng::with_seed(self.rng);
+        let placed: Vec<(i32, i32)> = board
+            .cells()
+            .filter(|(_, t)| t.placed)
+            .map(|(p, _)| *p)
+            .collect();
+        let mut candidates: Vec<((i32, i32), i32)> = board
+            .cells()
+            .filter(|((x, y), t)| {
+                let neighbours: Vec<Option<&Tile>> = Side::ALL
+                    .iter()
+                    .map(|s| s.step((*x, *y)))
+                    .map(|(x, y)| board.get(x, y))
+                    .collect();
+                !t.placed
+                    && !board.is_open(*x, *y)
+                    && neighbours.iter().any(Option::is_some)
+                    && neighbours.iter().flatten().all(|t| !t.placed)
+            })
+            .map(|((x, y), _)| {
+                let distance = placed
+                    .iter()
+                    .map(|(px, py)| (px - x).abs() + (py - y).abs())
+                    .min()
+                    .unwrap_or(0);
+                ((*x, *y), distance)
+            })
+            .filter(|(_, d)| (HOUSE_DISTANCE.0..=HOUSE_DISTANCE.1).contains(d))
+            .collect();
+        candidates.sort_unstable();
+        let house = (!candidates.is_empty()).then(|| {
+            let ((x, y), distance) = candidates[rng.usize(..candidates.len())];
+            let sides: Vec<Side> = Side::ALL
+                .into_iter()
+                .filter(|s| {
+                    let (x, y) = s.step((x, y));
+                    board.contains(x, y)
+                })
+                .collect();
+            let mut tile = Tile::empty();
+            *tile.border_mut(sides[rng.usize(..sides.len())]) = Border::Road;
+            House {
+                x,
+                y,
+                tile,
+                patience: PATIENCE + 2 * distance as usize,
+            }
+        });
+        self.next = match house {
+            Some(_) => {
+                self.houses += 1;
+                turn + INTERVAL.1.saturating_sub(self.houses / 3).max(INTERVAL.0)
+            }
+            None => turn + 1,
+        };
+        self.rng = rng.get_seed();
+        house
+    }
+}
+
+/// The cells missing from the board within `MARGIN` of a position
+pub fn grow(board: &Board, (x, y): (i32, i32)) -> Vec<(i32, i32)> {
+    (x - MARGIN..=x + MARGIN)
+        .flat_map(|x| (y - MARGIN..=y + MARGIN).map(move |y| (x, y)))
+        .filter(|(x, y)| !board.contains(*x, *y))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Spawn houses for `turns` turns, adding each one to the board
+    fn spawn_all(seed: u64, turns: usize) -> (Board, Vec<House>) {
+        let mut board = level(seed).board();
+        let mut spawner = Spawner::new(seed);
+        let mut houses = vec![];
+        for turn in 0..turns {
+            if let Some(house) = spawner.spawn(&board, turn) {
+                for (x, y) in grow(&board, (house.x, house.y)) {
+                    board.add_cell(x, y);
+                }
+                board
+                    .place(house.x, house.y, house.tile.clone(), false)
+                    .unwrap();
+                houses.push(house);
+            }
+        }
+        (board, houses)
+    }
+
+    #[test]
+    fn spawns_are_deterministic() {
+        let (_, houses) = spawn_all(5, 40);
+        assert!(houses.len() >= 5);
+        assert_eq!(spawn_all(5, 40).1, houses);
+        assert_ne!(spawn_all(6, 40).1, houses);
+    }
+
+    #[test]
+    fn houses_appear_away_from_the_network() {
+        let board = level(3).board();
+        let mut spawner = Spawner::new(3);
+        assert!(spawner.spawn(&board, 0).is_some());
+        assert!(spawner.next_turn() >= INTERVAL.0);
+        assert_eq!(spawner.spawn(&board, spawner.next_turn() - 1), None);
+
+        let (board, houses) = spawn_all(3, 60);
+        for house in houses {
+            let distance = house.x.abs() + house.y.abs();
+            assert!(distance >= HOUSE_DISTANCE.0, "{:?}", house);
+            assert!(board.get(house.x, house.y).is_some_and(|t| t.placed));
+            for side in Side::ALL {
+                let (x, y) = side.step((house.x, house.y));
+                assert!(board.get(x, y).is_some_and(|t| !t.placed));
+            }
+            assert!(house.patience >= PATIENCE + 2 * HOUSE_DISTANCE.0 as usize);
+        }
+    }
+
+    #[test]
+    fn grow_only_adds_missing_cells() {
+        let mut board = level(1).board();
+        assert!(grow(&board, (0, 0)).is_empty());
+        let cells = grow(&board, (2, 1));
+        assert_eq!(cells.len(), 7 * 7 - 5 * 6);
+        for (x, y) in cells {
+            assert!(!board.contains(x, y));
+            assert!((x - 2).abs() <= MARGIN && (y - 1).abs() <= MARGIN);
+            board.add_cell(x, y);
+        }
+        assert!(grow(&board, (2, 1)).is_empty());
+    }
+}
@@ -0,0 +1,342 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+use fastrand::Rng;
+
+use crate::level::{LevelData, Stars};
+use crate::solver::Solver;
+use crate::tile::{Border, Side, Tile};
+use crate::{daily, endless};
+
+/// How many positions the solver may visit when checking a generated level
+const SOLVER_NODES: usize = 20_000;
+/// How many levels are generated before giving up on finding a winnable one
+const MAX_ATTEMPTS: usize = 50;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "Easy"),
+            Difficulty::Normal => write!(f, "Normal"),
+            Difficulty::Hard => write!(f, "Hard"),
+        }
+    }
+}
+
+struct Settings {
+    width: (i32, i32),
+    height: (i32, i32),
+    houses: (usize, usize),
+    holes: f32,
+    weights: [(&'static str, f32); 5],
+}
+
+impl Difficulty {
+    fn settings(self) -> Settings {
+        match self {
+            Difficulty::Easy => Settings {
+                width: (5, 7),
+                height: (4, 6),
+                houses: (1, 1),
+                holes: 0.0,
+                weights: [
+                    ("tr", 0.3),
+                    ("tb", 0.3),
+                    ("trb", 0.3),
+                    ("trbl", 0.2),
+                    ("t", 0.0),
+                ],
+            },
+            Difficulty::Normal => Settings {
+                width: (7, 10),
+                height: (5, 8),
+                houses: (1, 2),
+                holes: 0.1,
+                weights: [
+                    ("tr", 0.3),
+                    ("tb", 0.3),
+                    ("trb", 0.2),
+                    ("trbl", 0.1),
+                    ("t", 0.1),
+                ],
+            },
+            Difficulty::Hard => Settings {
+                width: (9, 13),
+                height: (7, 10),
+                houses: (2, 4),
+                holes: 0.2,
+                weights: [
+                    ("tr", 0.3),
+                    ("tb", 0.3),
+                    ("trb", 0.15),
+                    ("trbl", 0.05),
+                    ("t", 0.2),
+                ],
+            },
+        }
+    }
+}
+
+/// Generate a level that is known to be winnable with its pile. The same
+/// seed and difficulty always give the same level. If no winnable level is
+/// found in `MAX_ATTEMPTS` tries, the last one is returned without stars.
+pub fn generate(seed: u64, difficulty: Difficulty) -> LevelData {
+    let rng = Rng::with_seed(seed);
+    let settings = difficulty.settings();
+    let mut level = LevelData::default();
+    for _ in 0..MAX_ATTEMPTS {
+        level = try_generate(&rng, &settings);
+        level.name = format!("{} #{}", difficulty, seed);
+        level.text = Some(format!("Random level, seed {}", seed));
+        if level.houses.is_empty() {
+            continue;
+        }
+        let winnable = Solver::from_level(&level)
+            .with_node_limit(SOLVER_NODES)
+            .solve_any();
+        if let Ok(Some(solution)) = winnable {
+            level.stars = Some(Stars::Par);
+            if level.resolve_stars().is_err() {
+                // Any solution still gives limits, if generous ones
+                level.stars = Some(Stars::from_par(solution.tiles + solution.skips));
+            }
+            return level;
+        }
+    }
+    level
+}
+
+/// Generate a level again from the name that `generate`, `daily::level`, or
+/// `endless::level` gave it
+pub fn regenerate(name: &str) -> Option<LevelData> {
+    if let Some(day) = daily::parse_name(name) {
+        return Some(daily::level(day));
+    }
+    let (difficulty, seed) = name.split_once(" #")?;
+    if difficulty == "Endless" {
+        return Some(endless::level(seed.parse().ok()?));
+    }
+    let difficulty = Difficulty::ALL
+        .into_iter()
+        .find(|d| d.to_string() == difficulty)?;
+    Some(generate(seed.parse().ok()?, difficulty))
+}
+
+fn neighbours(cells: &HashSet<(i32, i32)>, pos: (i32, i32)) -> Vec<Side> {
+    Side::ALL
+        .into_iter()
+        .filter(|s| cells.contains(&s.step(pos)))
+        .collect()
+}
+
+fn is_connected(cells: &HashSet<(i32, i32)>) -> bool {
+    distances(cells, *cells.iter().next().unwrap()).len() == cells.len()
+}
+
+/// Walking distances from `start` to every cell
+fn distances(cells: &HashSet<(i32, i32)>, start: (i32, i32)) -> Vec<((i32, i32), usize)> {
+    let mut visited = HashSet::from([start]);
+    let mut queue = VecDeque::from([(start, 0)]);
+    let mut result = vec![];
+    while let Some((pos, d)) = queue.pop_front() {
+        result.push((pos, d));
+        for side in neighbours(cells, pos) {
+            let next = side.step(pos);
+            if visited.insert(next) {
+                queue.push_back((next, d + 1));
+            }
+        }
+    }
+    result
+}
+
+/// A blob that grows from the middle
+fn blob(rng: &Rng, width: i32, height: i32) -> HashSet<(i32, i32)> {
+    let target = (width * height * 2 / 3) as usize;
+    let mut cells = HashSet::from([(width / 2, height / 2)]);
+    let mut list = vec![(width / 2, height / 2)];
+    while cells.len() < target {
+        let pos = list[rng.usize(..list.len())];
+        let (x, y) = Side::ALL[rng.usize(..4)].step(pos);
+        if x >= 0 && y >= 0 && x < width && y < height && cells.insert((x, y)) {
+            list.push((x, y));
+        }
+    }
+    cells
+}
+
+/// Crossing roads of width one or two
+fn corridors(rng: &Rng, width: i32, height: i32) -> HashSet<(i32, i32)> {
+    let mut cells = HashSet::new();
+    for _ in 0..rng.usize(2..4) {
+        let y = rng.i32(0..height - 1);
+        let thick = rng.i32(1..3);
+        for x in 0..width {
+            for dy in 0..thick {
+                cells.insert((x, y + dy));
+            }
+        }
+    }
+    for _ in 0..rng.usize(2..4) {
+        let x = rng.i32(0..width - 1);
+        let thick = rng.i32(1..3);
+        for y in 0..height {
+            for dx in 0..thick {
+                cells.insert((x + dx, y));
+            }
+        }
+    }
+    cells
+}
+
+/// A rectangle with a grid of pillars removed
+fn pillars(rng: &Rng, width: i32, height: i32) -> HashSet<(i32, i32)> {
+    let (ox, oy) = (rng.i32(0..3), rng.i32(0..3));
+    let mut cells = HashSet::new();
+    for x in 0..width {
+        for y in 0..height {
+            if (x + ox) % 3 != 2 || (y + oy) % 3 != 2 {
+                cells.insert((x, y));
+            }
+        }
+    }
+    cells
+}
+
+/// Remove random cells as long as the board stays in one piece
+fn punch_holes(rng: &Rng, cells: &mut HashSet<(i32, i32)>, amount: f32) {
+    let mut list: Vec<(i32, i32)> = cells.iter().copied().collect();
+    list.sort_unstable();
+    rng.shuffle(&mut list);
+    let count = (list.len() as f32 * amount) as usize;
+    for pos in list.into_iter().take(count) {
+        cells.remove(&pos);
+        if cells.is_empty() || !is_connected(cells) {
+            cells.insert(pos);
+        }
+    }
+}
+
+/// Roads towards one or more of the neighbouring cells
+fn road_stub(rng: &Rng, cells: &HashSet<(i32, i32)>, pos: (i32, i32), max: usize) -> Tile {
+    let mut sides = neighbours(cells, pos);
+    rng.shuffle(&mut sides);
+    let count = rng.usize(1..=sides.len().min(max));
+    let mut tile = Tile::default();
+    for side in Side::ALL {
+        *tile.border_mut(side) = if sides[..count].contains(&side) {
+            Border::Road
+        } else {
+            Border::Empty
+        };
+    }
+    tile
+}
+
+fn try_generate(rng: &Rng, settings: &Settings) -> LevelData {
+    let width = rng.i32(settings.width.0..=settings.width.1);
+    let height = rng.i32(settings.height.0..=settings.height.1);
+    let mut cells = match rng.usize(0..3) {
+        0 => blob(rng, width, height),
+        1 => corridors(rng, width, height),
+        _ => pillars(rng, width, height),
+    };
+    if !is_connected(&cells) {
+        cells = blob(rng, width, height);
+    }
+    punch_holes(rng, &mut cells, settings.holes);
+
+    let mut level = LevelData::default();
+    let mut sorted: Vec<(i32, i32)> = cells.iter().copied().collect();
+    sorted.sort_unstable();
+    let candidates: Vec<(i32, i32)> = sorted
+        .iter()
+        .copied()
+        .filter(|p| neighbours(&cells, *p).len() >= 2)
+        .collect();
+    let depot = candidates[rng.usize(..candidates.len())];
+    level
+        .depots
+        .push((depot.0, depot.1, road_stub(rng, &cells, depot, 3)));
+
+    // Houses are spread out, away from the depot and from each other
+    let dist = distances(&cells, depot);
+    let far = dist.iter().map(|(_, d)| *d).max().unwrap_or(0) / 2;
+    let mut taken = vec![depot];
+    for _ in 0..rng.usize(settings.houses.0..=settings.houses.1) {
+        let options: Vec<(i32, i32)> = dist
+            .iter()
+            .filter(|(p, d)| {
+                *d >= far.max(2)
+                    && taken
+                        .iter()
+                        .all(|t| (t.0 - p.0).abs() + (t.1 - p.1).abs() >= 3)
+            })
+            .map(|(p, _)| *p)
+            .collect();
+        if options.is_empty() {
+            break;
+        }
+        let house = options[rng.usize(..options.len())];
+        level
+            .houses
+            .push((house.0, house.1, road_stub(rng, &cells, house, 2)));
+        taken.push(house);
+    }
+
+    for (pattern, weight) in settings.weights {
+        let weight = ((weight + rng.f32() * 0.1 - 0.05) * 100.0).round().max(0.0) / 100.0;
+        if weight > 0.0 {
+            level.tiles.push((weight, Tile::create(pattern)));
+        }
+    }
+    level.fill = Some((100, rng.u64(..)));
+    level.cells = sorted;
+    level.camera = Some(((width - 1) as f32 * 0.5, (height - 1) as f32 * 0.5));
+    level
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_gives_the_same_level() {
+        for difficulty in Difficulty::ALL {
+            let level = generate(7, difficulty).to_string();
+            assert_eq!(generate(7, difficulty).to_string(), level);
+            assert_ne!(generate(8, difficulty).to_string(), level);
+        }
+    }
+
+    #[test]
+    fn generated_levels_are_winnable() {
+        let level = generate(3, Difficulty::Normal);
+        assert!(!level.houses.is_empty());
+        assert!(!level.star_limits().is_empty());
+        let solution = Solver::from_level(&level).solve_any().unwrap();
+        assert!(solution.is_some());
+    }
+
+    #[test]
+    fn regenerate_reads_the_name() {
+        let level = generate(42, Difficulty::Hard);
+        assert_eq!(level.name, "Hard #42");
+        let again = regenerate(&level.name).unwrap();
+        assert_eq!(again.to_string(), level.to_string());
+        assert!(regenerate("Tricky #42").is_none());
+        assert!(regenerate("Hard #many").is_none());
+    }
+}
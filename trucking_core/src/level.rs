@@ -0,0 +1,701 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::board::Board;
+use crate::deck::Deck;
+use crate::solver::{SolveError, Solver};
+use crate::terrain::Terrain;
+use crate::tile::Tile;
+
+/// How many positions the solver may visit when computing the par of a level
+const PAR_NODES: usize = 20_000;
+
+/// A level as described by a level file:
+///
+/// ```text
+/// # Comments start with a hash
+/// name: Two houses
+/// text: Optional hint shown at the top of the screen
+/// origin: -1 -3
+/// camera: 2 4
+/// depot @: trb
+/// depot $: l
+/// house A: bl
+/// house B: tl
+/// goods @: red
+/// goods $: blue
+/// goods B: blue
+/// separate: on
+/// deadline B: 8
+/// tiles: default
+/// tile tb: 0.1
+/// tile trbl=: 0.05
+/// pile: lr lr
+/// fill: 100 43
+/// undo: 3
+/// hints: 1
+/// stars: 6 8 12
+/// preview: 2
+/// finite: on
+/// skips: 3
+/// moves: 20
+/// endless: on
+/// throughput: 12 90s
+/// capacity: 2
+/// map:
+/// ..."A
+/// @.^^$
+/// .~~.B
+/// ```
+///
+/// The `map` is a grid where `.` is a cell and `#` (or a space) is no cell.
+/// Cells can also be forest `"`, hills `^`, or water `~`, see [`Terrain`].
+/// Depots and houses are marked in the grid with the symbol from their header
+/// line. The bottom left character of the grid is placed at `origin`.
+/// Depots and houses can be given `goods` of a color, such houses have to be
+/// connected to a depot with the same goods (other houses take any goods).
+/// With `separate: on` the roads from depots with different goods must not
+/// meet.
+/// A house with a `deadline` has to be connected within that many turns, or
+/// seconds when written like `30s`, otherwise the level is lost.
+/// The deck is built from the `tile` weights (`tiles: default` adds the
+/// standard set), then the `pile` tiles are put on top, and finally the
+/// pile is filled with `fill: <count> <seed>`.
+/// A tile pattern ending with `=`, like `tb=` or `trbl=`, is a bridge.
+/// Undoing placements is unlimited unless capped with `undo: <count>`
+/// (`undo: off` disables it). Hints work the same way with `hints`.
+/// The `stars` are the most tiles that may be drawn for three, two, and one
+/// stars. With `stars: par` they are computed by the solver instead, which
+/// only works for small levels.
+/// `preview` is how many tiles after the top one are shown, or `all` to show
+/// the whole pile.
+/// With `finite: on` the pile is not refilled with random tiles when it runs
+/// out. `skips` limits how many tiles may be skipped (`skips: off` disables
+/// skipping), and `moves` limits how many tiles may be drawn in total.
+/// An `endless` level has no edges, the board grows as the roads are built
+/// and new houses keep appearing.
+/// With `throughput: <parcels> <seconds>s` the connected houses keep ordering
+/// parcels, and that many have to be delivered in time, counted from when the
+/// first house is connected. A road tile fits
+/// `capacity` trucks before the traffic on it slows down.
+#[derive(Default, Debug, Clone)]
+pub struct LevelData {
+    pub name: String,
+    pub text: Option<String>,
+    pub camera: Option<(f32, f32)>,
+    pub cells: Vec<(i32, i32)>,
+    pub terrain: Vec<(i32, i32, Terrain)>,
+    pub depots: Vec<(i32, i32, Tile)>,
+    pub houses: Vec<(i32, i32, Tile)>,
+    pub deadlines: Vec<(i32, i32, Deadline)>,
+    pub goods: Vec<(i32, i32, Goods)>,
+    pub separate: bool,
+    pub tiles: Vec<(f32, Tile)>,
+    pub pile: Vec<Tile>,
+    pub fill: Option<(usize, u64)>,
+    pub undo_limit: Option<usize>,
+    pub hint_limit: Option<usize>,
+    pub stars: Option<Stars>,
+    pub preview: usize,
+    pub finite: bool,
+    pub skip_limit: Option<usize>,
+    pub move_limit: Option<usize>,
+    pub endless: bool,
+    pub throughput: Option<Throughput>,
+    pub capacity: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stars {
+    Limits(Vec<usize>),
+    Par,
+}
+
+impl Stars {
+    /// Limits for a level that can be won with `par` tiles
+    pub fn from_par(par: usize) -> Self {
+        Stars::Limits(vec![par, par + par.div_ceil(2), par * 2])
+    }
+}
+
+/// How long a house waits to be connected, counted from when it appears
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Deadline {
+    Turns(usize),
+    Seconds(f32),
+}
+
+impl fmt::Display for Deadline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Deadline::Turns(n) => write!(f, "{}", n),
+            Deadline::Seconds(s) => write!(f, "{}s", s),
+        }
+    }
+}
+
+/// How many parcels have to be delivered, and in how many seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Throughput {
+    pub parcels: usize,
+    pub seconds: f32,
+}
+
+impl fmt::Display for Throughput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}s", self.parcels, self.seconds)
+    }
+}
+
+/// The kinds of goods that are delivered, told apart by color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Goods {
+    Red,
+    Blue,
+    Yellow,
+    Purple,
+}
+
+impl Goods {
+    pub const ALL: [Goods; 4] = [Goods::Red, Goods::Blue, Goods::Yellow, Goods::Purple];
+}
+
+impl fmt::Display for Goods {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Goods::Red => write!(f, "red"),
+            Goods::Blue => write!(f, "blue"),
+            Goods::Yellow => write!(f, "yellow"),
+            Goods::Purple => write!(f, "purple"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub(crate) fn error<T>(line: usize, message: impl Into<String>) -> Result<T, ParseError> {
+    Err(ParseError {
+        line,
+        message: message.into(),
+    })
+}
+
+pub(crate) fn parse_pattern(line: usize, pattern: &str) -> Result<Tile, ParseError> {
+    if pattern.is_empty() || !pattern.bytes().all(|c| b"trbl=".contains(&c)) {
+        return error(line, format!("invalid tile pattern '{}'", pattern));
+    }
+    let tile = Tile::create(pattern);
+    if tile.bridge && !tile.is_straight() && tile.roads() != 4 {
+        return error(line, format!("the bridge '{}' is not straight", pattern));
+    }
+    Ok(tile)
+}
+
+pub(crate) fn parse_number<T: std::str::FromStr>(
+    line: usize,
+    value: &str,
+) -> Result<T, ParseError> {
+    value
+        .parse()
+        .or_else(|_| error(line, format!("invalid number '{}'", value)))
+}
+
+fn parse_pair<A: std::str::FromStr, B: std::str::FromStr>(
+    line: usize,
+    value: &str,
+) -> Result<(A, B), ParseError> {
+    let mut parts = value.split_whitespace();
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(a), Some(b), None) => Ok((parse_number(line, a)?, parse_number(line, b)?)),
+        _ => error(line, format!("expected two numbers, got '{}'", value)),
+    }
+}
+
+fn parse_symbol(line: usize, arg: Option<&str>) -> Result<u8, ParseError> {
+    match arg.map(str::as_bytes) {
+        Some([c]) if !b"# ".contains(c) && Terrain::from_symbol(*c).is_none() => Ok(*c),
+        _ => error(line, "expected a single map symbol"),
+    }
+}
+
+fn parse_goods(line: usize, value: &str) -> Result<Goods, ParseError> {
+    match Goods::ALL.into_iter().find(|g| g.to_string() == value) {
+        Some(goods) => Ok(goods),
+        None => error(line, format!("unknown goods '{}'", value)),
+    }
+}
+
+fn parse_throughput(line: usize, value: &str) -> Result<Throughput, ParseError> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let [parcels, seconds] = parts[..] else {
+        return error(line, "expected 'throughput: <parcels> <seconds>s'");
+    };
+    let Some(seconds) = seconds.strip_suffix('s') else {
+        return error(line, "expected 'throughput: <parcels> <seconds>s'");
+    };
+    Ok(Throughput {
+        parcels: parse_number(line, parcels)?,
+        seconds: parse_number(line, seconds)?,
+    })
+}
+
+pub(crate) fn parse_on_off(line: usize, value: &str) -> Result<bool, ParseError> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => error(line, format!("expected 'on' or 'off', got '{}'", value)),
+    }
+}
+
+const DEPOT_SYMBOLS: &[u8] = b"@$%&*";
+const HOUSE_SYMBOLS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+impl fmt::Display for LevelData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "name: {}", self.name)?;
+        if let Some(text) = &self.text {
+            writeln!(f, "text: {}", text)?;
+        }
+        let min_x = self.cells.iter().map(|(x, _)| *x).min().unwrap_or(0);
+        let max_x = self.cells.iter().map(|(x, _)| *x).max().unwrap_or(0);
+        let min_y = self.cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+        let max_y = self.cells.iter().map(|(_, y)| *y).max().unwrap_or(0);
+        writeln!(f, "origin: {} {}", min_x, min_y)?;
+        if let Some((x, y)) = self.camera {
+            writeln!(f, "camera: {} {}", x, y)?;
+        }
+        let mut symbols = HashMap::new();
+        let objectives = [
+            ("depot", &self.depots, DEPOT_SYMBOLS),
+            ("house", &self.houses, HOUSE_SYMBOLS),
+        ];
+        for (key, list, chars) in objectives {
+            for ((x, y, tile), c) in list.iter().zip(chars.iter()) {
+                writeln!(f, "{} {}: {}", key, *c as char, tile.pattern())?;
+                symbols.insert((*x, *y), *c as char);
+            }
+        }
+        for (_, list, chars) in objectives {
+            for ((x, y, _), c) in list.iter().zip(chars.iter()) {
+                if let Some(goods) = self.goods(*x, *y) {
+                    writeln!(f, "goods {}: {}", *c as char, goods)?;
+                }
+            }
+        }
+        if self.separate {
+            writeln!(f, "separate: on")?;
+        }
+        for ((x, y, _), c) in self.houses.iter().zip(HOUSE_SYMBOLS.iter()) {
+            if let Some(deadline) = self.deadline(*x, *y) {
+                writeln!(f, "deadline {}: {}", *c as char, deadline)?;
+            }
+        }
+        for (p, t) in self.tiles.iter() {
+            writeln!(f, "tile {}: {}", t.pattern(), p)?;
+        }
+        if !self.pile.is_empty() {
+            let pile: Vec<String> = self.pile.iter().map(Tile::pattern).collect();
+            writeln!(f, "pile: {}", pile.join(" "))?;
+        }
+        if let Some((num, seed)) = self.fill {
+            writeln!(f, "fill: {} {}", num, seed)?;
+        }
+        match self.undo_limit {
+            Some(0) => writeln!(f, "undo: off")?,
+            Some(n) => writeln!(f, "undo: {}", n)?,
+            None => {}
+        }
+        match self.hint_limit {
+            Some(0) => writeln!(f, "hints: off")?,
+            Some(n) => writeln!(f, "hints: {}", n)?,
+            None => {}
+        }
+        match &self.stars {
+            Some(Stars::Limits(limits)) => {
+                let limits: Vec<String> = limits.iter().map(usize::to_string).collect();
+                writeln!(f, "stars: {}", limits.join(" "))?;
+            }
+            Some(Stars::Par) => writeln!(f, "stars: par")?,
+            None => {}
+        }
+        match self.preview {
+            0 => {}
+            usize::MAX => writeln!(f, "preview: all")?,
+            n => writeln!(f, "preview: {}", n)?,
+        }
+        if self.finite {
+            writeln!(f, "finite: on")?;
+        }
+        match self.skip_limit {
+            Some(0) => writeln!(f, "skips: off")?,
+            Some(n) => writeln!(f, "skips: {}", n)?,
+            None => {}
+        }
+        if let Some(n) = self.move_limit {
+            writeln!(f, "moves: {}", n)?;
+        }
+        if self.endless {
+            writeln!(f, "endless: on")?;
+        }
+        if let Some(throughput) = self.throughput {
+            writeln!(f, "throughput: {}", throughput)?;
+        }
+        if let Some(n) = self.capacity {
+            writeln!(f, "capacity: {}", n)?;
+        }
+        writeln!(f, "map:")?;
+        let cells: HashSet<&(i32, i32)> = self.cells.iter().collect();
+        for y in (min_y..=max_y).rev() {
+            let row: String = (min_x..=max_x)
+                .map(|x| match symbols.get(&(x, y)) {
+                    Some(c) => *c,
+                    None if cells.contains(&(x, y)) => self.terrain(x, y).symbol() as char,
+                    None => '#',
+                })
+                .collect();
+            writeln!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+impl LevelData {
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut level = LevelData::default();
+        let mut origin = (0, 0);
+        let mut objectives: Vec<(u8, bool, Tile)> = vec![];
+        let mut deadlines: Vec<(u8, Deadline)> = vec![];
+        let mut goods: Vec<(u8, Goods)> = vec![];
+        let mut lines = text.lines().enumerate().map(|(i, l)| (i + 1, l));
+        let mut map = vec![];
+        for (n, line) in lines.by_ref() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                return error(n, "expected 'key: value'");
+            };
+            let value = value.trim();
+            let mut key = key.split_whitespace();
+            match (key.next(), key.next()) {
+                (Some("map"), None) => {
+                    map.extend(lines.by_ref());
+                    break;
+                }
+                (Some("name"), None) => level.name = value.to_string(),
+                (Some("text"), None) => level.text = Some(value.to_string()),
+                (Some("origin"), None) => origin = parse_pair(n, value)?,
+                (Some("camera"), None) => level.camera = Some(parse_pair(n, value)?),
+                (Some("depot"), arg) => {
+                    objectives.push((parse_symbol(n, arg)?, true, parse_pattern(n, value)?))
+                }
+                (Some("house"), arg) => {
+                    objectives.push((parse_symbol(n, arg)?, false, parse_pattern(n, value)?))
+                }
+                (Some("deadline"), arg) => {
+                    let deadline = match value.strip_suffix('s') {
+                        Some(seconds) => Deadline::Seconds(parse_number(n, seconds)?),
+                        None => Deadline::Turns(parse_number(n, value)?),
+                    };
+                    deadlines.push((parse_symbol(n, arg)?, deadline));
+                }
+                (Some("goods"), arg) => goods.push((parse_symbol(n, arg)?, parse_goods(n, value)?)),
+                (Some("separate"), None) => level.separate = parse_on_off(n, value)?,
+                (Some("tiles"), None) if value == "default" => {
+                    let mut deck = Deck::default();
+                    deck.add_all_tiles();
+                    level.tiles.extend(deck.weights().iter().cloned());
+                }
+                (Some("tile"), Some(pattern)) => {
+                    let tile = parse_pattern(n, pattern)?;
+                    level.tiles.push((parse_number(n, value)?, tile));
+                }
+                (Some("pile"), None) => {
+                    for p in value.split_whitespace() {
+                        level.pile.push(parse_pattern(n, p)?);
+                    }
+                }
+                (Some("fill"), None) => level.fill = Some(parse_pair(n, value)?),
+                (Some("undo"), None) if value == "off" => level.undo_limit = Some(0),
+                (Some("undo"), None) => level.undo_limit = Some(parse_number(n, value)?),
+                (Some("hints"), None) if value == "off" => level.hint_limit = Some(0),
+                (Some("hints"), None) => level.hint_limit = Some(parse_number(n, value)?),
+                (Some("finite"), None) => level.finite = parse_on_off(n, value)?,
+                (Some("skips"), None) if value == "off" => level.skip_limit = Some(0),
+                (Some("skips"), None) => level.skip_limit = Some(parse_number(n, value)?),
+                (Some("moves"), None) => level.move_limit = Some(parse_number(n, value)?),
+                (Some("endless"), None) => level.endless = parse_on_off(n, value)?,
+                (Some("throughput"), None) => level.throughput = Some(parse_throughput(n, value)?),
+                (Some("capacity"), None) => match parse_number(n, value)? {
+                    0 => return error(n, "the capacity must be at least one"),
+                    c => level.capacity = Some(c),
+                },
+                (Some("preview"), None) if value == "all" => level.preview = usize::MAX,
+                (Some("preview"), None) => level.preview = parse_number(n, value)?,
+                (Some("stars"), None) if value == "par" => level.stars = Some(Stars::Par),
+                (Some("stars"), None) => {
+                    let limits = value
+                        .split_whitespace()
+                        .map(|v| parse_number(n, v))
+                        .collect::<Result<Vec<usize>, _>>()?;
+                    if limits.is_empty() || limits.len() > 3 {
+                        return error(n, "expected one to three star limits");
+                    }
+                    if limits.windows(2).any(|w| w[0] > w[1]) {
+                        return error(n, "the star limits must not decrease");
+                    }
+                    level.stars = Some(Stars::Limits(limits));
+                }
+                _ => return error(n, format!("unknown key '{}'", line)),
+            }
+        }
+        let height = map.len() as i32;
+        for (row, (n, line)) in map.into_iter().enumerate() {
+            let y = origin.1 + height - 1 - row as i32;
+            for (col, c) in line.bytes().enumerate() {
+                let x = origin.0 + col as i32;
+                match c {
+                    b'#' | b' ' | b'\r' => continue,
+                    b'.' => {}
+                    _ if Terrain::from_symbol(c).is_some() => {
+                        level.terrain.push((x, y, Terrain::from_symbol(c).unwrap()))
+                    }
+                    _ => {
+                        match objectives.iter().find(|(s, _, _)| *s == c) {
+                            Some((_, true, tile)) => level.depots.push((x, y, tile.clone())),
+                            Some((_, false, tile)) => {
+                                level.houses.push((x, y, tile.clone()));
+                                if let Some((_, d)) = deadlines.iter().find(|(s, _)| *s == c) {
+                                    level.deadlines.push((x, y, *d));
+                                }
+                            }
+                            None => return error(n, format!("unknown map symbol '{}'", c as char)),
+                        }
+                        if let Some((_, g)) = goods.iter().find(|(s, _)| *s == c) {
+                            level.goods.push((x, y, *g));
+                        }
+                    }
+                }
+                level.cells.push((x, y));
+            }
+        }
+        if level.depots.is_empty() {
+            return error(0, "the map has no depot");
+        }
+        for (symbol, _) in deadlines.iter() {
+            if !objectives.iter().any(|(s, depot, _)| s == symbol && !depot) {
+                return error(
+                    0,
+                    format!("the deadline for '{}' has no house", *symbol as char),
+                );
+            }
+        }
+        for (symbol, _) in goods.iter() {
+            if !objectives.iter().any(|(s, _, _)| s == symbol) {
+                return error(
+                    0,
+                    format!("the goods for '{}' have no depot or house", *symbol as char),
+                );
+            }
+        }
+        for (x, y, _) in level.houses.iter() {
+            if let Some(g) = level.goods(*x, *y) {
+                if !level
+                    .depots
+                    .iter()
+                    .any(|(dx, dy, _)| level.goods(*dx, *dy) == Some(g))
+                {
+                    return error(0, format!("no depot has the {} goods", g));
+                }
+            }
+        }
+        if let Err((x, y)) = level.try_board() {
+            return error(0, format!("the roads at x={} y={} lead off the map", x, y));
+        }
+        Ok(level)
+    }
+
+    fn try_board(&self) -> Result<Board, (i32, i32)> {
+        let mut board = Board::default();
+        for (x, y) in self.cells.iter() {
+            board.add_cell(*x, *y);
+        }
+        for (x, y, terrain) in self.terrain.iter() {
+            board.set_terrain(*x, *y, *terrain);
+        }
+        if !self.endless {
+            board.apply_borders();
+        }
+        for (x, y, tile) in self.depots.iter() {
+            board
+                .place(*x, *y, tile.clone(), true)
+                .map_err(|_| (*x, *y))?;
+        }
+        for (x, y, tile) in self.houses.iter() {
+            board
+                .place(*x, *y, tile.clone(), false)
+                .map_err(|_| (*x, *y))?;
+        }
+        Ok(board)
+    }
+
+    /// Build the board with the depots and houses already placed
+    pub fn board(&self) -> Board {
+        self.try_board().expect("Could not place the objectives")
+    }
+
+    pub fn deck(&self) -> Deck {
+        let mut deck = Deck::default();
+        for (p, t) in self.tiles.iter() {
+            deck.add_tile(t.clone(), *p);
+        }
+        for t in self.pile.iter() {
+            deck.add_to_pile(t.clone());
+        }
+        if let Some((num, seed)) = self.fill {
+            deck.fill_pile(num, seed);
+        }
+        deck.set_finite(self.finite);
+        deck
+    }
+
+    pub fn deadline(&self, x: i32, y: i32) -> Option<Deadline> {
+        self.deadlines
+            .iter()
+            .find(|(dx, dy, _)| (*dx, *dy) == (x, y))
+            .map(|(_, _, d)| *d)
+    }
+
+    pub fn terrain(&self, x: i32, y: i32) -> Terrain {
+        self.terrain
+            .iter()
+            .find(|(tx, ty, _)| (*tx, *ty) == (x, y))
+            .map(|(_, _, t)| *t)
+            .unwrap_or_default()
+    }
+
+    pub fn goods(&self, x: i32, y: i32) -> Option<Goods> {
+        self.goods
+            .iter()
+            .find(|(gx, gy, _)| (*gx, *gy) == (x, y))
+            .map(|(_, _, g)| *g)
+    }
+
+    /// The tile limits for the stars, missing limits are always earned
+    pub fn star_limits(&self) -> &[usize] {
+        match &self.stars {
+            Some(Stars::Limits(limits)) => limits,
+            _ => &[],
+        }
+    }
+
+    /// Replace `stars: par` with limits based on the shortest solution. Fails
+    /// if the solver cannot find the shortest solution in time, a level that
+    /// cannot be won at all gets no limits.
+    pub fn resolve_stars(&mut self) -> Result<(), SolveError> {
+        if self.stars != Some(Stars::Par) {
+            return Ok(());
+        }
+        let solution = Solver::from_level(self)
+            .with_node_limit(PAR_NODES)
+            .solve()?;
+        self.stars = solution.map(|solution| Stars::from_par(solution.tiles + solution.skips));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL: &str = "\
+name: Two houses
+text: Connect both houses
+origin: -1 -3
+camera: 2 4
+depot @: trb
+depot $: l
+house A: l
+house B: l
+goods @: red
+goods $: blue
+goods B: blue
+separate: on
+deadline B: 8
+tile tb: 0.1
+tile trbl=: 0.05
+pile: rl rl
+fill: 10 43
+undo: 3
+hints: off
+stars: 6 8 12
+preview: 2
+finite: on
+skips: 3
+moves: 20
+throughput: 12 90s
+capacity: 2
+map:
+...\"A
+@.^^$
+.~~.B
+";
+
+    #[test]
+    fn parse_reads_every_key() {
+        let level = LevelData::parse(LEVEL).unwrap();
+        assert_eq!(level.name, "Two houses");
+        assert_eq!(level.camera, Some((2., 4.)));
+        assert_eq!(level.cells.len(), 15);
+        assert_eq!(
+            level.depots,
+            vec![(-1, -2, Tile::create("trb")), (3, -2, Tile::create("l"))]
+        );
+        assert_eq!(
+            level.houses,
+            vec![(3, -1, Tile::create("l")), (3, -3, Tile::create("l"))]
+        );
+        assert_eq!(level.terrain(0, -3), Terrain::Water);
+        assert_eq!(level.deadline(3, -3), Some(Deadline::Turns(8)));
+        assert_eq!(level.goods(3, -3), Some(Goods::Blue));
+        assert_eq!(level.goods(3, -1), None);
+        assert_eq!(level.pile.len(), 2);
+        assert_eq!(level.fill, Some((10, 43)));
+        assert_eq!(level.undo_limit, Some(3));
+        assert_eq!(level.hint_limit, Some(0));
+        assert_eq!(level.star_limits(), [6, 8, 12]);
+        assert_eq!(level.skip_limit, Some(3));
+        assert_eq!(level.move_limit, Some(20));
+        assert_eq!(level.capacity, Some(2));
+        assert!(level.separate && level.finite && !level.endless);
+        assert_eq!(level.deck().pile().len(), 12);
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let level = LevelData::parse(LEVEL).unwrap();
+        let text = level.to_string();
+        assert_eq!(text, LEVEL);
+        assert_eq!(LevelData::parse(&text).unwrap().to_string(), text);
+    }
+
+    #[test]
+    fn parse_reports_the_line() {
+        let err = LevelData::parse("name: Broken\nstars: many\nmap:\n.").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(LevelData::parse("name: Empty\nmap:\n").is_err());
+    }
+}
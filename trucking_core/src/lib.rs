@@ -0,0 +1,23 @@
+pub mod board;
+pub mod daily;
+pub mod deck;
+pub mod endless;
+pub mod generator;
+pub mod level;
+pub mod replay;
+pub mod save;
+pub mod score;
+pub mod solver;
+pub mod terrain;
+pub mod tile;
+pub mod traffic;
+
+pub use board::Board;
+pub use deck::Deck;
+pub use level::LevelData;
+pub use replay::Replay;
+pub use save::SaveData;
+pub use score::Score;
+pub use solver::Solver;
+pub use terrain::Terrain;
+pub use tile::{Border, Side, Tile};
@@ -0,0 +1,143 @@
+use std::fmt;
+
+use crate::level::{error, parse_number, parse_pattern, ParseError};
+use crate::tile::Tile;
+
+/// Something the player did, in the order it was done
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Turn the top tile until it looks like this
+    Rotate(Tile),
+    Skip,
+    Hold,
+    Place(i32, i32),
+    Undo,
+    Redo,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Rotate(tile) => write!(f, "rotate {}", tile.pattern()),
+            Action::Skip => write!(f, "skip"),
+            Action::Hold => write!(f, "hold"),
+            Action::Place(x, y) => write!(f, "place {} {}", x, y),
+            Action::Undo => write!(f, "undo"),
+            Action::Redo => write!(f, "redo"),
+        }
+    }
+}
+
+/// A recorded session of a level:
+///
+/// ```text
+/// level: Two houses
+/// seed: 1234
+/// 0.8 rotate tr
+/// 2.5 place 1 0
+/// 3.1 skip
+/// ```
+///
+/// `level` is the name of the level and `seed` the random state of the deck
+/// when the level started. Every other line is the time in seconds and the
+/// action that was taken.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Replay {
+    pub level: String,
+    pub seed: u64,
+    pub actions: Vec<(f32, Action)>,
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "level: {}", self.level)?;
+        writeln!(f, "seed: {}", self.seed)?;
+        for (time, action) in self.actions.iter() {
+            writeln!(f, "{:.2} {}", time, action)?;
+        }
+        Ok(())
+    }
+}
+
+impl Replay {
+    pub fn new(level: &str, seed: u64) -> Self {
+        Self {
+            level: level.to_string(),
+            seed,
+            actions: vec![],
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut replay = Replay::default();
+        for (n, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                match key {
+                    "level" => replay.level = value.trim().to_string(),
+                    "seed" => replay.seed = parse_number(n, value.trim())?,
+                    _ => return error(n, format!("unknown key '{}'", line)),
+                }
+                continue;
+            }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let action = match parts[1..] {
+                ["rotate", pattern] => Action::Rotate(parse_pattern(n, pattern)?),
+                ["skip"] => Action::Skip,
+                ["hold"] => Action::Hold,
+                ["place", x, y] => Action::Place(parse_number(n, x)?, parse_number(n, y)?),
+                ["undo"] => Action::Undo,
+                ["redo"] => Action::Redo,
+                _ => return error(n, format!("unknown action '{}'", line)),
+            };
+            replay.actions.push((parse_number(n, parts[0])?, action));
+        }
+        if replay.level.is_empty() {
+            return error(0, "the replay has no level");
+        }
+        Ok(replay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_round_trips() {
+        let mut replay = Replay::new("Two houses", 1234);
+        replay.actions = vec![
+            (0.25, Action::Rotate(Tile::create("tr"))),
+            (1.5, Action::Place(1, -2)),
+            (2., Action::Skip),
+            (2.75, Action::Hold),
+            (3., Action::Undo),
+            (3.5, Action::Redo),
+        ];
+        let text = replay.to_string();
+        assert!(text.starts_with("level: Two houses\nseed: 1234\n0.25 rotate tr\n"));
+        assert_eq!(Replay::parse(&text).unwrap(), replay);
+    }
+
+    #[test]
+    fn parse_skips_comments() {
+        let replay = Replay::parse("# a replay\nlevel: Detour\n\n1.0 skip\n").unwrap();
+        assert_eq!(replay.level, "Detour");
+        assert_eq!(replay.seed, 0);
+        assert_eq!(replay.actions, vec![(1., Action::Skip)]);
+    }
+
+    #[test]
+    fn parse_reports_the_line() {
+        let line = |text| Replay::parse(text).unwrap_err().line;
+        assert_eq!(line("level: Detour\nspeed: 2\n"), 2);
+        assert_eq!(line("level: Detour\n1.0 jump\n"), 2);
+        assert_eq!(line("level: Detour\n1.0 place 1\n"), 2);
+        assert_eq!(line("level: Detour\nseed: many\n"), 2);
+        assert_eq!(line("level: Detour\nsoon skip\n"), 2);
+        assert_eq!(line("level: Detour\n1.0 place one 2\n"), 2);
+        assert_eq!(line("seed: 1\n1.0 skip\n"), 0);
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::daily::Streak;
+use crate::level::{error, parse_number, parse_on_off, ParseError};
+use crate::score::Score;
+
+/// The version of the save files written by this build
+pub const SAVE_VERSION: u32 = 3;
+
+/// Progress and settings as stored in a save file:
+///
+/// ```text
+/// version: 3
+/// music: on
+/// sounds: off
+/// level: 3 6 42.5 2885 Two houses
+/// daily: 20744 4 9 2 17 95.0 -
+/// ```
+///
+/// A `level` line has the most stars, the fewest tiles, the fastest time in
+/// seconds, and the most points (see `Score::points`) for a completed level,
+/// followed by the name of the level. The points are `-` for records from
+/// before version 3.
+/// The `daily` line has the last completed day, the current and the longest
+/// streak, and the results of that day.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SaveData {
+    pub levels: BTreeMap<String, Record>,
+    pub settings: Settings,
+    pub daily: Streak,
+}
+
+/// The best results for a level, not necessarily from the same attempt
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Record {
+    pub stars: usize,
+    pub tiles: usize,
+    pub seconds: f32,
+    pub points: Option<usize>,
+}
+
+impl Record {
+    pub fn new(score: &Score, stars: usize) -> Self {
+        Self {
+            stars,
+            tiles: score.tiles(),
+            seconds: score.seconds,
+            points: Some(score.points(stars)),
+        }
+    }
+
+    /// The best of both records
+    pub fn best(&self, other: &Record) -> Record {
+        Record {
+            stars: self.stars.max(other.stars),
+            tiles: self.tiles.min(other.tiles),
+            seconds: self.seconds.min(other.seconds),
+            points: match (self.points, other.points) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+    pub music: bool,
+    pub sounds: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music: true,
+            sounds: true,
+        }
+    }
+}
+
+/// Points that are written as `-` when they are not known
+struct Points(Option<usize>);
+
+impl fmt::Display for Points {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(d) => write!(f, "{}", d),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+fn parse_points(n: usize, value: &str) -> Result<Option<usize>, ParseError> {
+    match value {
+        "-" => Ok(None),
+        _ => parse_number(n, value).map(Some),
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+impl fmt::Display for SaveData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "version: {}", SAVE_VERSION)?;
+        writeln!(f, "music: {}", on_off(self.settings.music))?;
+        writeln!(f, "sounds: {}", on_off(self.settings.sounds))?;
+        for (name, record) in self.levels.iter() {
+            writeln!(
+                f,
+                "level: {} {} {:.1} {} {}",
+                record.stars,
+                record.tiles,
+                record.seconds,
+                Points(record.points),
+                name
+            )?;
+        }
+        if let Some(record) = self.daily.record {
+            writeln!(
+                f,
+                "daily: {} {} {} {} {} {:.1} {}",
+                self.daily.day,
+                self.daily.length,
+                self.daily.longest,
+                record.stars,
+                record.tiles,
+                record.seconds,
+                Points(record.points)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl SaveData {
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut save = SaveData::default();
+        let mut version = None;
+        for (n, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                return error(n, "expected 'key: value'");
+            };
+            let value = value.trim();
+            match key.trim() {
+                "version" => {
+                    let v: u32 = parse_number(n, value)?;
+                    if v == 0 || v > SAVE_VERSION {
+                        return error(n, format!("unsupported save version {}", v));
+                    }
+                    version = Some(v);
+                }
+                _ if version.is_none() => return error(n, "the save has no version"),
+                "music" => save.settings.music = parse_on_off(n, value)?,
+                "sounds" => save.settings.sounds = parse_on_off(n, value)?,
+                "level" => {
+                    let old = version.is_some_and(|v| v < 3);
+                    let mut parts = value.splitn(if old { 4 } else { 5 }, ' ');
+                    let (Some(stars), Some(tiles), Some(seconds), Some(points)) = (
+                        parts.next(),
+                        parts.next(),
+                        parts.next(),
+                        if old { Some("-") } else { parts.next() },
+                    ) else {
+                        return error(
+                            n,
+                            "expected 'level: <stars> <tiles> <seconds> <points> <name>'",
+                        );
+                    };
+                    let record = Record {
+                        stars: parse_number(n, stars)?,
+                        tiles: parse_number(n, tiles)?,
+                        seconds: parse_number(n, seconds)?,
+                        points: parse_points(n, points)?,
+                    };
+                    save.levels
+                        .insert(parts.next().unwrap_or("").to_string(), record);
+                }
+                "daily" => {
+                    let mut parts: Vec<&str> = value.split_whitespace().collect();
+                    if version.is_some_and(|v| v < 3) {
+                        parts.push("-");
+                    }
+                    let [day, length, longest, stars, tiles, seconds, points] = parts[..] else {
+                        return error(
+                            n,
+                            "expected 'daily: <day> <streak> <longest> <stars> <tiles> <seconds> <points>'",
+                        );
+                    };
+                    save.daily = Streak {
+                        day: parse_number(n, day)?,
+                        length: parse_number(n, length)?,
+                        longest: parse_number(n, longest)?,
+                        record: Some(Record {
+                            stars: parse_number(n, stars)?,
+                            tiles: parse_number(n, tiles)?,
+                            seconds: parse_number(n, seconds)?,
+                            points: parse_points(n, points)?,
+                        }),
+                    };
+                }
+                _ => return error(n, format!("unknown key '{}'", line)),
+            }
+        }
+        if version.is_none() {
+            return error(0, "the save has no version");
+        }
+        Ok(save)
+    }
+
+    pub fn completed(&self, name: &str) -> bool {
+        self.levels.contains_key(name)
+    }
+
+    /// Keep the best results for a level, returns false if nothing improved
+    pub fn record(&mut self, name: &str, score: &Score, stars: usize) -> bool {
+        let new = Record::new(score, stars);
+        let best = match self.levels.get(name) {
+            Some(old) => old.best(&new),
+            None => new,
+        };
+        self.levels.insert(name.to_string(), best) != Some(best)
+    }
+
+    /// Complete a daily level, returns false if nothing changed
+    pub fn record_daily(&mut self, day: i64, score: &Score, stars: usize) -> bool {
+        self.daily.complete(day, Record::new(score, stars))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(placed: usize, seconds: f32) -> Score {
+        Score {
+            placed,
+            seconds,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse_reads_settings_and_levels() {
+        let save = SaveData::parse(
+            "version: 1\n# a comment\nmusic: off\nsounds: on\nlevel: 3 6 42.5 Two houses\n",
+        )
+        .unwrap();
+        assert_eq!(
+            save.settings,
+            Settings {
+                music: false,
+                sounds: true
+            }
+        );
+        let record = save.levels["Two houses"];
+        assert_eq!((record.stars, record.tiles, record.seconds), (3, 6, 42.5));
+        assert!(save.completed("Two houses"));
+        assert!(!save.completed("Two"));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let mut save = SaveData::default();
+        save.settings.sounds = false;
+        save.record("First steps", &score(4, 12.), 3);
+        save.record("Long haul", &score(60, 300.5), 1);
+        let text = save.to_string();
+        assert_eq!(SaveData::parse(&text).unwrap(), save);
+    }
+
+    #[test]
+    fn old_saves_are_upgraded() {
+        let save = SaveData::parse(
+            "version: 2\nmusic: on\nsounds: on\nlevel: 2 9 61.0 Detour\ndaily: 20744 4 9 2 17 95.0\n",
+        )
+        .unwrap();
+        let record = save.levels["Detour"];
+        assert_eq!((record.stars, record.tiles, record.points), (2, 9, None));
+        let daily = save.daily.record.unwrap();
+        assert_eq!(
+            (save.daily.length, daily.tiles, daily.points),
+            (4, 17, None)
+        );
+        let text = save.to_string();
+        assert!(text.starts_with(&format!("version: {}\n", SAVE_VERSION)));
+        assert!(text.contains("level: 2 9 61.0 - Detour\n"));
+        assert!(text.contains("daily: 20744 4 9 2 17 95.0 -\n"));
+        assert_eq!(SaveData::parse(&text).unwrap(), save);
+    }
+
+    #[test]
+    fn parse_rejects_bad_saves() {
+        assert!(SaveData::parse("music: on\n").is_err());
+        assert!(SaveData::parse("").is_err());
+        assert!(SaveData::parse(&format!("version: {}\n", SAVE_VERSION + 1)).is_err());
+        assert!(SaveData::parse("version: 1\nvolume: 11\n").is_err());
+        assert_eq!(
+            SaveData::parse("version: 1\nlevel: 3 six 1.0 Name\n")
+                .unwrap_err()
+                .line,
+            2
+        );
+    }
+
+    #[test]
+    fn record_keeps_the_best_results() {
+        let mut save = SaveData::default();
+        assert!(save.record("Level", &score(10, 30.), 2));
+        assert!(save.record("Level", &score(12, 20.), 1));
+        assert!(!save.record("Level", &score(12, 40.), 1));
+        let record = save.levels["Level"];
+        assert_eq!((record.stars, record.tiles, record.seconds), (2, 10, 20.));
+    }
+}
@@ -0,0 +1,91 @@
+/// Tiles a delivery truck drives in a second
+pub const TRUCK_SPEED: f32 = 4.;
+
+/// Points for every star earned
+pub const STAR_POINTS: usize = 1000;
+/// Points lost for every tile drawn from the pile
+pub const TILE_COST: usize = 10;
+/// Points lost for every tile driven by the delivery trucks
+pub const DISTANCE_COST: usize = 5;
+
+/// How a level was played
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Score {
+    pub placed: usize,
+    pub skipped: usize,
+    pub rotations: usize,
+    pub undos: usize,
+    pub seconds: f32,
+    /// Tiles driven by the delivery trucks after the level was completed
+    pub distance: usize,
+    /// Seconds until the last parcel arrived
+    pub delivery: f32,
+}
+
+impl Score {
+    /// The number of tiles drawn from the pile
+    pub fn tiles(&self) -> usize {
+        self.placed + self.skipped
+    }
+
+    /// Count the routes of the delivery trucks, given in tiles driven
+    pub fn count_routes(&mut self, routes: impl IntoIterator<Item = usize>) {
+        self.distance = 0;
+        self.delivery = 0.;
+        for route in routes {
+            self.distance += route;
+            self.delivery = self.delivery.max(arrival(route));
+        }
+    }
+
+    /// Stars earned with the limits from `LevelData::star_limits`
+    pub fn stars(&self, limits: &[usize]) -> usize {
+        let earned = limits.iter().filter(|l| self.tiles() <= **l).count();
+        (3 + earned).saturating_sub(limits.len())
+    }
+
+    /// Points for a completed level, a short network with few tiles scores
+    /// higher than a sprawling one
+    pub fn points(&self, stars: usize) -> usize {
+        (stars * STAR_POINTS)
+            .saturating_sub(self.tiles() * TILE_COST)
+            .saturating_sub(self.distance * DISTANCE_COST)
+    }
+}
+
+/// Seconds until a truck has driven a route of `length` tiles
+pub fn arrival(length: usize) -> f32 {
+    length as f32 / TRUCK_SPEED
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stars_follow_the_limits() {
+        let score = Score {
+            placed: 8,
+            skipped: 2,
+            ..Default::default()
+        };
+        assert_eq!(score.stars(&[20, 10]), 3);
+        assert_eq!(score.stars(&[12, 9]), 2);
+        assert_eq!(score.stars(&[9, 8]), 1);
+    }
+
+    #[test]
+    fn short_networks_score_higher() {
+        let mut short = Score {
+            placed: 10,
+            ..Default::default()
+        };
+        let mut sprawling = short.clone();
+        short.count_routes([3, 5]);
+        sprawling.count_routes([3, 12]);
+        assert_eq!((short.distance, short.delivery), (8, 1.25));
+        assert!(short.points(3) > sprawling.points(3));
+        assert!(short.points(2) < sprawling.points(3));
+        assert_eq!(Score::default().points(0), 0);
+    }
+}
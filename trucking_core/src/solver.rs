@@ -0,0 +1,771 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use crate::board::Board;
+use crate::level::{Goods, LevelData};
+use crate::tile::{Border, Side, Tile};
+
+/// One step through the pile: either place the top tile or skip it
+#[derive(Debug, Clone, PartialEq)]
+pub enum Move {
+    Place { x: i32, y: i32, tile: Tile },
+    Skip,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Move::Place { x, y, tile } => write!(f, "place {} at {} {}", tile.pattern(), x, y),
+            Move::Skip => write!(f, "skip"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub moves: Vec<Move>,
+    pub tiles: usize,
+    pub skips: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolveError {
+    /// The search was stopped before it could give an answer
+    NodeLimit,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::NodeLimit => write!(f, "the search ran out of nodes"),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+/// Searches for the shortest way to connect all houses to a depot using the
+/// tiles of the pile in order. Only the known pile is searched, not the
+/// random tiles that are drawn after it runs out.
+///
+/// Solutions use as few tiles as possible, and then as few skips as possible.
+pub struct Solver {
+    board: Board,
+    grid: Grid,
+    depots: Vec<(i32, i32)>,
+    houses: Vec<(i32, i32)>,
+    groups: Vec<Group>,
+    pile: Vec<Tile>,
+    /// For every pile index, the next index with a tile of each shape
+    next: Vec<Vec<usize>>,
+    /// Whether the pile has a crossing bridge, which lets roads pass each other
+    crossing: bool,
+    node_limit: Option<usize>,
+    nodes: usize,
+    end: usize,
+    hash: u64,
+    /// Boards that could not be won from an index with a budget of tiles
+    failed: HashMap<u64, Vec<(usize, usize)>>,
+    placed: Vec<(usize, i32, i32, Tile)>,
+}
+
+/// Houses that have to be connected to one of the depots
+struct Group {
+    depots: Vec<(i32, i32)>,
+    houses: Vec<(i32, i32)>,
+    /// Depots with other goods that the roads must not reach
+    rivals: Vec<(i32, i32)>,
+}
+
+/// The splitmix64 finalizer
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Boards are hashed by xoring the hashes of the placed tiles
+fn tile_hash(x: i32, y: i32, tile: &Tile) -> u64 {
+    let pos = ((x as u32 as u64) << 32) | (y as u32 as u64);
+    mix(mix(pos) ^ u32::from(tile) as u64)
+}
+
+fn rotations(tile: &Tile) -> Vec<Tile> {
+    let mut rotations = vec![tile.clone()];
+    for _ in 0..3 {
+        let next = rotations[rotations.len() - 1].rotate90();
+        if !rotations.contains(&next) {
+            rotations.push(next);
+        }
+    }
+    rotations
+}
+
+impl Solver {
+    /// The board should already have the depots and houses placed on it
+    pub fn new(
+        board: Board,
+        depots: Vec<(i32, i32)>,
+        houses: Vec<(i32, i32)>,
+        pile: Vec<Tile>,
+    ) -> Self {
+        let shape = |t: &Tile| rotations(t).iter().map(u32::from).min();
+        let mut shapes = vec![];
+        for tile in pile.iter() {
+            if !shapes.contains(&shape(tile)) {
+                shapes.push(shape(tile));
+            }
+        }
+        let mut next = vec![vec![pile.len(); shapes.len()]; pile.len() + 1];
+        for (i, tile) in pile.iter().enumerate().rev() {
+            next[i] = next[i + 1].clone();
+            let s = shapes.iter().position(|s| *s == shape(tile)).unwrap();
+            next[i][s] = i;
+        }
+        let crossing = pile.iter().any(|t| t.bridge && t.roads() == 4);
+        Self {
+            grid: Grid::new(&board),
+            board,
+            groups: vec![Group {
+                depots: depots.clone(),
+                houses: houses.clone(),
+                rivals: vec![],
+            }],
+            depots,
+            houses,
+            pile,
+            next,
+            crossing,
+            node_limit: None,
+            nodes: 0,
+            end: 0,
+            hash: 0,
+            failed: HashMap::new(),
+            placed: vec![],
+        }
+    }
+
+    pub fn from_level(level: &LevelData) -> Self {
+        Self::new(
+            level.board(),
+            level.depots.iter().map(|(x, y, _)| (*x, *y)).collect(),
+            level.houses.iter().map(|(x, y, _)| (*x, *y)).collect(),
+            level.deck().pile().iter().cloned().collect(),
+        )
+        .with_goods(&level.goods, level.separate)
+    }
+
+    /// Houses with goods can only be connected to depots with the same goods.
+    /// With `separate` the roads from depots with different goods must not meet.
+    pub fn with_goods(mut self, goods: &[(i32, i32, Goods)], separate: bool) -> Self {
+        let goods: HashMap<(i32, i32), Goods> =
+            goods.iter().map(|(x, y, g)| ((*x, *y), *g)).collect();
+        let kind = |pos: &(i32, i32)| goods.get(pos).copied();
+        let mut kinds: Vec<Option<Goods>> = self.depots.iter().map(kind).collect();
+        kinds.extend(self.houses.iter().map(kind));
+        kinds.sort_unstable();
+        kinds.dedup();
+        self.groups = kinds
+            .into_iter()
+            .map(|k| {
+                let (depots, rivals): (Vec<_>, Vec<_>) = self
+                    .depots
+                    .iter()
+                    .partition(|d| k.is_none() || kind(d) == k);
+                let rivals = match k {
+                    Some(_) if separate => {
+                        rivals.into_iter().filter(|d| kind(d).is_some()).collect()
+                    }
+                    _ => vec![],
+                };
+                Group {
+                    depots,
+                    houses: self
+                        .houses
+                        .iter()
+                        .filter(|h| kind(h) == k)
+                        .copied()
+                        .collect(),
+                    rivals,
+                }
+            })
+            .collect();
+        self
+    }
+
+    /// Give up after visiting this many positions
+    pub fn with_node_limit(mut self, limit: usize) -> Self {
+        self.node_limit = Some(limit);
+        self
+    }
+
+    /// The number of positions visited so far
+    pub fn nodes(&self) -> usize {
+        self.nodes
+    }
+
+    /// Find the solution with the fewest tiles, returns `Ok(None)` if the
+    /// level cannot be won with the pile
+    pub fn solve(&mut self) -> Result<Option<Solution>, SolveError> {
+        self.nodes = 0;
+        self.failed.clear();
+        let Some(tiles) = self.min_tiles(self.pile.len())? else {
+            return Ok(None);
+        };
+        // Fewest tiles drawn from the pile that still allows the fewest placements
+        let mut solution = self.solution();
+        let (mut low, mut high) = (tiles, self.pile.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.search_with(mid, tiles)? {
+                high = mid;
+                solution = self.solution();
+            } else {
+                low = mid + 1;
+            }
+        }
+        Ok(Some(solution))
+    }
+
+    /// Quickly find some way to win, without trying to use few tiles
+    pub fn solve_any(&mut self) -> Result<Option<Solution>, SolveError> {
+        self.nodes = 0;
+        self.failed.clear();
+        self.reset(self.pile.len());
+        let found = self.greedy(0);
+        self.finish(found)?;
+        Ok(found.then(|| self.solution()))
+    }
+
+    /// A good move for the top tile of the pile: the first move of a
+    /// solution if one is found, otherwise the placement that gets closest
+    /// to connecting the houses
+    pub fn next_move(&mut self) -> Option<Move> {
+        let solution = match self.solve() {
+            Ok(None) | Err(_) => self.solve_any(),
+            result => result,
+        };
+        if let Ok(Some(solution)) = solution {
+            return solution.moves.into_iter().next();
+        }
+        self.reset(self.pile.len());
+        let mut best = None;
+        for (i, x, y, tile) in self.moves(0) {
+            if i != 0 {
+                continue;
+            }
+            self.place(i, x, y, tile.clone());
+            if let Some(h) = self.estimate() {
+                if best.as_ref().is_none_or(|(b, _)| h < *b) {
+                    best = Some((h, Move::Place { x, y, tile }));
+                }
+            }
+            self.take_back();
+        }
+        match best {
+            Some((_, m)) => Some(m),
+            None if self.pile.is_empty() => None,
+            None => Some(Move::Skip),
+        }
+    }
+
+    fn solution(&self) -> Solution {
+        let mut moves = vec![];
+        for (index, x, y, tile) in self.placed.iter() {
+            moves.resize(*index, Move::Skip);
+            moves.push(Move::Place {
+                x: *x,
+                y: *y,
+                tile: tile.clone(),
+            });
+        }
+        Solution {
+            tiles: self.placed.len(),
+            skips: moves.len() - self.placed.len(),
+            moves,
+        }
+    }
+
+    /// Iterative deepening on the number of placed tiles
+    fn min_tiles(&mut self, end: usize) -> Result<Option<usize>, SolveError> {
+        let Some(mut budget) = self.estimate() else {
+            return Ok(None);
+        };
+        while budget <= end {
+            if self.search_with(end, budget)? {
+                return Ok(Some(budget));
+            }
+            budget += 1;
+        }
+        Ok(None)
+    }
+
+    fn search_with(&mut self, end: usize, budget: usize) -> Result<bool, SolveError> {
+        self.reset(end);
+        let found = self.search(0, budget);
+        self.finish(found)?;
+        Ok(found)
+    }
+
+    fn reset(&mut self, end: usize) {
+        if self.end != end {
+            self.failed.clear();
+            self.end = end;
+        }
+        self.placed.clear();
+    }
+
+    /// Take back the placements of a found solution, they are kept in `placed`
+    fn finish(&mut self, found: bool) -> Result<(), SolveError> {
+        if found {
+            for (_, x, y, tile) in self.placed.iter().rev() {
+                self.board
+                    .unplace(*x, *y)
+                    .expect("Could not take back a tile");
+                self.grid.update(&self.board, (*x, *y));
+                self.hash ^= tile_hash(*x, *y, tile);
+            }
+        }
+        if self.node_limit.is_some_and(|l| self.nodes > l) {
+            return Err(SolveError::NodeLimit);
+        }
+        Ok(())
+    }
+
+    /// Every placement of the next tile of each shape. Skipping is always
+    /// possible, so there is no need to try skipping every tile one by one.
+    fn moves(&self, index: usize) -> Vec<(usize, i32, i32, Tile)> {
+        let mut slots: Vec<(i32, i32)> = self.board.open_slots().copied().collect();
+        slots.sort_unstable();
+        let mut next = self.next[index].clone();
+        next.retain(|i| *i < self.end);
+        next.sort_unstable();
+        let mut moves = vec![];
+        for i in next {
+            for (x, y) in slots.iter() {
+                for tile in rotations(&self.pile[i]) {
+                    if self.board.can_place(*x, *y, &tile) {
+                        moves.push((i, *x, *y, tile));
+                    }
+                }
+            }
+        }
+        moves
+    }
+
+    fn place(&mut self, index: usize, x: i32, y: i32, tile: Tile) {
+        self.board
+            .place(x, y, tile.clone(), true)
+            .expect("Could not place a tile");
+        self.grid.update(&self.board, (x, y));
+        self.hash ^= tile_hash(x, y, &tile);
+        self.placed.push((index, x, y, tile));
+    }
+
+    fn take_back(&mut self) {
+        let (_, x, y, tile) = self.placed.pop().expect("No tile to take back");
+        self.board
+            .unplace(x, y)
+            .expect("Could not take back a tile");
+        self.grid.update(&self.board, (x, y));
+        self.hash ^= tile_hash(x, y, &tile);
+    }
+
+    /// Check whether the board is known to be lost from an index with the
+    /// budget. Since skipping is always possible, a board that cannot be won
+    /// from an index cannot be won from later ones either.
+    fn has_failed(&self, index: usize, budget: usize) -> bool {
+        self.failed
+            .get(&self.hash)
+            .is_some_and(|f| f.iter().any(|(i, b)| *i <= index && *b >= budget))
+    }
+
+    fn set_failed(&mut self, index: usize, budget: usize) {
+        let failed = self.failed.entry(self.hash).or_default();
+        failed.retain(|(i, b)| !(*i >= index && *b <= budget));
+        failed.push((index, budget));
+    }
+
+    fn out_of_nodes(&mut self) -> bool {
+        self.nodes += 1;
+        self.node_limit.is_some_and(|l| self.nodes > l)
+    }
+
+    /// Depth first search with at most `budget` more tiles
+    fn search(&mut self, index: usize, budget: usize) -> bool {
+        if self.out_of_nodes() {
+            return false;
+        }
+        match self.estimate() {
+            Some(0) => return true,
+            Some(h) if h <= budget && h <= self.end.saturating_sub(index) => {}
+            _ => return false,
+        }
+        if self.has_failed(index, budget) {
+            return false;
+        }
+        for (i, x, y, tile) in self.moves(index) {
+            self.place(i, x, y, tile);
+            if self.search(i + 1, budget - 1) {
+                return true;
+            }
+            self.take_back();
+        }
+        self.set_failed(index, budget);
+        false
+    }
+
+    /// Depth first search that tries the most promising placements first
+    fn greedy(&mut self, index: usize) -> bool {
+        if self.out_of_nodes() {
+            return false;
+        }
+        match self.estimate() {
+            Some(0) => return true,
+            Some(h) if h <= self.end.saturating_sub(index) => {}
+            _ => return false,
+        }
+        if self.has_failed(index, usize::MAX) {
+            return false;
+        }
+        let mut moves = vec![];
+        for (i, x, y, tile) in self.moves(index) {
+            self.place(i, x, y, tile);
+            if let Some(h) = self.estimate() {
+                moves.push((h, self.placed[self.placed.len() - 1].clone()));
+            }
+            self.take_back();
+        }
+        moves.sort_by_key(|(h, (i, _, _, _))| (*h, *i));
+        for (_, (i, x, y, tile)) in moves {
+            self.place(i, x, y, tile);
+            if self.greedy(i + 1) {
+                return true;
+            }
+            self.take_back();
+        }
+        self.set_failed(index, usize::MAX);
+        false
+    }
+
+    /// A lower bound for the number of tiles needed to connect every house,
+    /// `None` if some house can no longer be connected.
+    fn estimate(&self) -> Option<usize> {
+        let mut bound = 0;
+        for group in self.groups.iter() {
+            let network = self.grid.network(&group.depots);
+            let mut rivals = group.rivals.iter().filter_map(|d| self.grid.index(*d));
+            if rivals.any(|d| network[d]) {
+                return None;
+            }
+            let blocked = self.grid.claimed(&group.rivals, self.crossing);
+            bound = bound.max(self.group_estimate(&network, &blocked, &group.houses)?);
+        }
+        Some(bound)
+    }
+
+    /// A road network connecting the depots and the houses can be walked
+    /// around, visiting every house and every cell once per branch. So the
+    /// shortest round trip through the houses gives a bound for the size.
+    fn group_estimate(
+        &self,
+        network: &[bool],
+        blocked: &[bool],
+        houses: &[(i32, i32)],
+    ) -> Option<usize> {
+        let houses: Vec<usize> = houses
+            .iter()
+            .filter_map(|h| self.grid.index(*h))
+            .filter(|h| !network[*h])
+            .collect();
+        if houses.is_empty() {
+            return Some(0);
+        }
+        let sources: Vec<usize> = (0..network.len()).filter(|i| network[*i]).collect();
+        let mut dist = vec![vec![0; houses.len() + 1]; houses.len() + 1];
+        let from_network = self.grid.distances(&sources, blocked);
+        for (i, h) in houses.iter().enumerate() {
+            let d = from_network[*h];
+            if d == usize::MAX {
+                return None;
+            }
+            dist[0][i + 1] = d;
+            dist[i + 1][0] = d;
+        }
+        for (i, a) in houses.iter().enumerate() {
+            let from_house = self.grid.distances(&[*a], blocked);
+            for (j, b) in houses.iter().enumerate() {
+                dist[i + 1][j + 1] = from_house[*b];
+            }
+        }
+        let mut bound = houses.len().max(dist[0].iter().copied().max().unwrap_or(0));
+        for i in 1..dist.len() {
+            for j in 1..i {
+                let tour = dist[0][i] + dist[i][j] + dist[j][0];
+                bound = bound.max((tour - 1).div_ceil(2));
+            }
+        }
+        if houses.len() > 2 && houses.len() <= 8 {
+            let mut best = usize::MAX;
+            shortest_tour(&dist, 0, 1, 0, &mut best);
+            bound = bound.max((best + 1 - houses.len()).div_ceil(2));
+        }
+        Some(bound)
+    }
+}
+
+/// Brute force the shortest round trip starting and ending at 0
+fn shortest_tour(dist: &[Vec<usize>], at: usize, visited: u32, length: usize, best: &mut usize) {
+    if length >= *best {
+        return;
+    }
+    if visited.count_ones() as usize == dist.len() {
+        *best = length + dist[at][0];
+        return;
+    }
+    for next in 1..dist.len() {
+        if visited & (1 << next) == 0 {
+            shortest_tour(
+                dist,
+                next,
+                visited | 1 << next,
+                length + dist[at][next],
+                best,
+            );
+        }
+    }
+}
+
+/// A dense copy of the board for quickly measuring distances
+struct Grid {
+    origin: (i32, i32),
+    width: i32,
+    height: i32,
+    cells: Vec<Option<Tile>>,
+}
+
+impl Grid {
+    fn new(board: &Board) -> Self {
+        let min_x = board.cells().map(|((x, _), _)| *x).min().unwrap_or(0);
+        let max_x = board.cells().map(|((x, _), _)| *x).max().unwrap_or(0);
+        let min_y = board.cells().map(|((_, y), _)| *y).min().unwrap_or(0);
+        let max_y = board.cells().map(|((_, y), _)| *y).max().unwrap_or(0);
+        let mut grid = Self {
+            origin: (min_x, min_y),
+            width: max_x - min_x + 1,
+            height: max_y - min_y + 1,
+            cells: vec![None; ((max_x - min_x + 1) * (max_y - min_y + 1)) as usize],
+        };
+        for (pos, tile) in board.cells() {
+            let i = grid.index(*pos).unwrap();
+            grid.cells[i] = Some(tile.clone());
+        }
+        grid
+    }
+
+    fn index(&self, (x, y): (i32, i32)) -> Option<usize> {
+        let (x, y) = (x - self.origin.0, y - self.origin.1);
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some((y * self.width + x) as usize)
+    }
+
+    fn neighbour(&self, i: usize, side: Side) -> Option<usize> {
+        let x = (i % self.width as usize) as i32 + self.origin.0;
+        let y = (i / self.width as usize) as i32 + self.origin.1;
+        self.index(side.step((x, y)))
+    }
+
+    /// Copy a cell and its neighbours from the board after it has changed
+    fn update(&mut self, board: &Board, pos: (i32, i32)) {
+        for p in std::iter::once(pos).chain(Side::ALL.map(|s| s.step(pos))) {
+            if let Some(i) = self.index(p) {
+                self.cells[i] = board.get(p.0, p.1).cloned();
+            }
+        }
+    }
+
+    /// Placed tiles that are connected by roads to a depot
+    fn network(&self, depots: &[(i32, i32)]) -> Vec<bool> {
+        self.follow(depots).0
+    }
+
+    /// Cells that the roads from the depots reach or lead into, so other
+    /// roads cannot use them without meeting. Crossing bridges are left out
+    /// since another road can still pass under them, and so are the empty
+    /// cells the roads lead into when a crossing bridge can still be placed.
+    fn claimed(&self, depots: &[(i32, i32)], crossing: bool) -> Vec<bool> {
+        let mut claimed = vec![false; self.cells.len()];
+        if depots.is_empty() {
+            return claimed;
+        }
+        let (visited, followed) = self.follow(depots);
+        for i in (0..self.cells.len()).filter(|i| visited[*i]) {
+            if !matches!(&self.cells[i], Some(t) if t.bridge && t.roads() == 4) {
+                claimed[i] = true;
+            }
+            for side in Side::ALL {
+                if crossing || followed[i] & 1 << side as u8 == 0 {
+                    continue;
+                }
+                if let Some(n) = self.neighbour(i, side) {
+                    if self.cells[n].as_ref().is_some_and(|t| !t.placed) {
+                        claimed[n] = true;
+                    }
+                }
+            }
+        }
+        claimed
+    }
+
+    /// The placed tiles reached from the depots, and which sides of each
+    /// tile the roads were followed through
+    fn follow(&self, depots: &[(i32, i32)]) -> (Vec<bool>, Vec<u8>) {
+        let mut visited = vec![false; self.cells.len()];
+        // The sides of each cell whose roads have been followed
+        let mut followed = vec![0u8; self.cells.len()];
+        let mut stack: Vec<(usize, Option<Side>)> = depots
+            .iter()
+            .filter_map(|d| self.index(*d))
+            .map(|i| (i, None))
+            .collect();
+        while let Some((i, from)) = stack.pop() {
+            let tile = match &self.cells[i] {
+                Some(t) if t.placed => t,
+                _ => continue,
+            };
+            visited[i] = true;
+            for side in tile.exits(from) {
+                if followed[i] & 1 << side as u8 == 0 {
+                    followed[i] |= 1 << side as u8;
+                    stack.extend(self.neighbour(i, side).map(|n| (n, Some(side.opposite()))));
+                }
+            }
+        }
+        (visited, followed)
+    }
+
+    /// The number of empty cells on the shortest path from the sources to
+    /// every cell, ignoring which tiles are left in the pile
+    fn distances(&self, sources: &[usize], blocked: &[bool]) -> Vec<usize> {
+        let mut dist = vec![usize::MAX; self.cells.len()];
+        let mut queue = VecDeque::new();
+        for i in sources {
+            dist[*i] = 0;
+            queue.push_back((*i, 0));
+        }
+        while let Some((i, d)) = queue.pop_front() {
+            if dist[i] < d {
+                continue;
+            }
+            let Some(tile) = &self.cells[i] else {
+                continue;
+            };
+            for side in Side::ALL {
+                match tile.border(side) {
+                    Border::Empty => continue,
+                    Border::Any if tile.placed => continue,
+                    _ => {}
+                }
+                let Some(next) = self.neighbour(i, side).filter(|n| !blocked[*n]) else {
+                    continue;
+                };
+                let Some(other) = &self.cells[next] else {
+                    continue;
+                };
+                let cost = match (other.placed, other.border(side.opposite())) {
+                    (_, Border::Empty) => continue,
+                    (true, Border::Road) => d,
+                    (true, _) => continue,
+                    (false, _) => d + 1,
+                };
+                if dist[next] <= cost {
+                    continue;
+                }
+                dist[next] = cost;
+                if cost == d {
+                    queue.push_front((next, cost));
+                } else {
+                    queue.push_back((next, cost));
+                }
+            }
+        }
+        dist
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A depot and a house three cells apart, with `pile` to connect them
+    fn corridor(pile: &str) -> LevelData {
+        LevelData::parse(&format!(
+            "name: Corridor\ndepot @: r\nhouse A: l\npile: {}\nmap:\n@...A\n",
+            pile
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn solve_finds_the_fewest_tiles_and_skips() {
+        let level = corridor("rl t rl trbl rl tb");
+        let solution = Solver::from_level(&level).solve().unwrap().unwrap();
+        // The crossing does not fit between the edges of the corridor
+        assert_eq!((solution.tiles, solution.skips), (3, 2));
+        assert_eq!(solution.moves[1], Move::Skip);
+        assert_eq!(solution.moves[3], Move::Skip);
+        assert_eq!(
+            solution.moves[0],
+            Move::Place {
+                x: 1,
+                y: 0,
+                tile: Tile::create("rl")
+            }
+        );
+    }
+
+    #[test]
+    fn solve_turns_the_tiles() {
+        let level = corridor("tb tb tb");
+        let solution = Solver::from_level(&level).solve().unwrap().unwrap();
+        assert_eq!((solution.tiles, solution.skips), (3, 0));
+    }
+
+    #[test]
+    fn solve_reports_unwinnable_piles() {
+        let level = corridor("rl t rl");
+        assert!(Solver::from_level(&level).solve().unwrap().is_none());
+        assert!(Solver::from_level(&level).solve_any().unwrap().is_none());
+    }
+
+    #[test]
+    fn separate_roads_cross_on_a_bridge() {
+        let level = LevelData::parse(
+            "name: Cross\ndepot @: r\ndepot $: b\nhouse A: l\nhouse B: t\n\
+             goods @: red\ngoods A: red\ngoods $: blue\ngoods B: blue\n\
+             separate: on\npile: trbl trbl=\nmap:\n#$#\n@.A\n#B#\n",
+        )
+        .unwrap();
+        let solution = Solver::from_level(&level).solve().unwrap().unwrap();
+        // The plain crossing would join the red and blue roads
+        assert_eq!((solution.tiles, solution.skips), (1, 1));
+        assert_eq!(
+            solution.moves[1],
+            Move::Place {
+                x: 1,
+                y: 1,
+                tile: Tile::create("trbl=")
+            }
+        );
+    }
+
+    #[test]
+    fn solve_stops_at_the_node_limit() {
+        let level = corridor("t t t t t t t t rl rl rl");
+        let mut solver = Solver::from_level(&level).with_node_limit(1);
+        assert_eq!(solver.solve().unwrap_err(), SolveError::NodeLimit);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::tile::Tile;
+
+/// What a cell is covered with, which limits the roads that can be built on it
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Terrain {
+    #[default]
+    Grass,
+    Forest,
+    Hills,
+    Water,
+}
+
+impl Terrain {
+    pub const ALL: [Terrain; 4] = [
+        Terrain::Grass,
+        Terrain::Forest,
+        Terrain::Hills,
+        Terrain::Water,
+    ];
+
+    /// The character used for the terrain in the level map
+    pub fn symbol(self) -> u8 {
+        match self {
+            Terrain::Grass => b'.',
+            Terrain::Forest => b'"',
+            Terrain::Hills => b'^',
+            Terrain::Water => b'~',
+        }
+    }
+
+    pub fn from_symbol(symbol: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.symbol() == symbol)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Terrain::Grass => "Grass",
+            Terrain::Forest => "Forest",
+            Terrain::Hills => "Hills",
+            Terrain::Water => "Water",
+        }
+    }
+
+    /// Whether a road tile can be built on this terrain: forests fit at most
+    /// two roads, hills only fit straight roads, and water needs a bridge
+    pub fn accepts(self, tile: &Tile) -> bool {
+        match self {
+            Terrain::Grass => true,
+            Terrain::Forest => tile.roads() <= 2,
+            Terrain::Hills => tile.is_straight(),
+            Terrain::Water => tile.bridge,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    fn accepts(terrain: Terrain, pattern: &str) -> bool {
+        terrain.accepts(&Tile::create(pattern))
+    }
+
+    #[test]
+    fn forest_takes_at_most_two_roads() {
+        assert!(accepts(Terrain::Forest, "t"));
+        assert!(accepts(Terrain::Forest, "tr"));
+        assert!(accepts(Terrain::Forest, "tb"));
+        assert!(!accepts(Terrain::Forest, "trb"));
+        assert!(!accepts(Terrain::Forest, "trbl"));
+    }
+
+    #[test]
+    fn hills_take_straight_roads() {
+        assert!(accepts(Terrain::Hills, "tb"));
+        assert!(accepts(Terrain::Hills, "rl"));
+        assert!(accepts(Terrain::Hills, "tb="));
+        assert!(!accepts(Terrain::Hills, "t"));
+        assert!(!accepts(Terrain::Hills, "tr"));
+        assert!(!accepts(Terrain::Hills, "trbl"));
+    }
+
+    #[test]
+    fn water_takes_bridges() {
+        assert!(accepts(Terrain::Water, "tb="));
+        assert!(accepts(Terrain::Water, "trbl="));
+        assert!(!accepts(Terrain::Water, "tb"));
+        assert!(!accepts(Terrain::Water, "trbl"));
+        assert!(accepts(Terrain::Grass, "trbl"));
+    }
+
+    #[test]
+    fn symbols_round_trip() {
+        for terrain in Terrain::ALL {
+            assert_eq!(Terrain::from_symbol(terrain.symbol()), Some(terrain));
+        }
+        assert_eq!(Terrain::from_symbol(b'#'), None);
+    }
+
+    #[test]
+    fn boards_enforce_terrain() {
+        let mut board = Board::default();
+        for x in 0..3 {
+            board.add_cell(x, 0);
+        }
+        board.set_terrain(1, 0, Terrain::Water);
+        assert!(board.can_place(0, 0, &Tile::create("tr")));
+        assert!(!board.can_place(1, 0, &Tile::create("rl")));
+        assert!(board.can_place(1, 0, &Tile::create("rl=")));
+        assert!(board.place(1, 0, Tile::create("rl"), true).is_err());
+        assert!(board.place(1, 0, Tile::create("rl="), true).is_ok());
+    }
+}
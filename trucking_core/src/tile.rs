@@ -0,0 +1,262 @@
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Tile {
+    pub placed: bool,
+    pub top: Border,
+    pub right: Border,
+    pub bottom: Border,
+    pub left: Border,
+    /// The roads of a bridge only lead straight across, so a crossing bridge
+    /// does not join the two roads. Bridges can also be built over water.
+    pub bridge: bool,
+}
+
+#[derive(Default, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Border {
+    #[default]
+    Any,
+    Empty,
+    Road,
+}
+
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum Side {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl Side {
+    pub const ALL: [Side; 4] = [Side::Top, Side::Right, Side::Bottom, Side::Left];
+
+    pub fn opposite(self) -> Self {
+        match self {
+            Side::Top => Side::Bottom,
+            Side::Right => Side::Left,
+            Side::Bottom => Side::Top,
+            Side::Left => Side::Right,
+        }
+    }
+
+    /// The position of the neighbour on this side of `(x, y)`
+    pub fn step(self, (x, y): (i32, i32)) -> (i32, i32) {
+        match self {
+            Side::Top => (x, y + 1),
+            Side::Right => (x + 1, y),
+            Side::Bottom => (x, y - 1),
+            Side::Left => (x - 1, y),
+        }
+    }
+}
+
+impl Tile {
+    pub fn border(&self, side: Side) -> Border {
+        match side {
+            Side::Top => self.top,
+            Side::Right => self.right,
+            Side::Bottom => self.bottom,
+            Side::Left => self.left,
+        }
+    }
+
+    pub fn border_mut(&mut self, side: Side) -> &mut Border {
+        match side {
+            Side::Top => &mut self.top,
+            Side::Right => &mut self.right,
+            Side::Bottom => &mut self.bottom,
+            Side::Left => &mut self.left,
+        }
+    }
+
+    pub fn has_road(&self) -> bool {
+        self.top == Border::Road
+            || self.right == Border::Road
+            || self.bottom == Border::Road
+            || self.left == Border::Road
+    }
+
+    /// The number of sides with roads
+    pub fn roads(&self) -> usize {
+        Side::ALL
+            .into_iter()
+            .filter(|s| self.border(*s) == Border::Road)
+            .count()
+    }
+
+    /// Whether the tile is a single road going straight through, "tb" or "lr"
+    pub fn is_straight(&self) -> bool {
+        self.roads() == 2 && self.top == self.bottom
+    }
+
+    /// The sides that a road entering the tile from `from` leads to, or all
+    /// the roads of the tile if the road starts here
+    pub fn exits(&self, from: Option<Side>) -> impl Iterator<Item = Side> + '_ {
+        Side::ALL.into_iter().filter(move |side| {
+            self.border(*side) == Border::Road
+                && match from {
+                    Some(from) if self.bridge => *side == from.opposite(),
+                    _ => true,
+                }
+        })
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            placed: false,
+            top: Border::Empty,
+            right: Border::Empty,
+            bottom: Border::Empty,
+            left: Border::Empty,
+            bridge: false,
+        }
+    }
+
+    /// A tile from a pattern like "trl", with a "=" for a bridge
+    pub fn create(pattern: &str) -> Self {
+        let mut new = Self::empty();
+        for v in pattern.as_bytes() {
+            match v {
+                b't' => new.top = Border::Road,
+                b'r' => new.right = Border::Road,
+                b'b' => new.bottom = Border::Road,
+                b'l' => new.left = Border::Road,
+                b'=' => new.bridge = true,
+                _ => {}
+            }
+        }
+        new
+    }
+
+    /// The inverse of `create`, the sides with roads as a string like "trl"
+    pub fn pattern(&self) -> String {
+        let mut pattern: String = Side::ALL
+            .into_iter()
+            .zip("trbl".chars())
+            .filter(|(s, _)| self.border(*s) == Border::Road)
+            .map(|(_, c)| c)
+            .collect();
+        if self.bridge {
+            pattern.push('=');
+        }
+        pattern
+    }
+
+    pub fn rotate270(&self) -> Self {
+        Self {
+            placed: self.placed,
+            top: self.right,
+            right: self.bottom,
+            bottom: self.left,
+            left: self.top,
+            bridge: self.bridge,
+        }
+    }
+
+    pub fn rotate90(&self) -> Self {
+        Self {
+            placed: self.placed,
+            top: self.left,
+            right: self.top,
+            bottom: self.right,
+            left: self.bottom,
+            bridge: self.bridge,
+        }
+    }
+
+    pub fn rotate180(&self) -> Self {
+        Self {
+            placed: self.placed,
+            top: self.bottom,
+            right: self.left,
+            bottom: self.top,
+            left: self.right,
+            bridge: self.bridge,
+        }
+    }
+
+    pub fn placeable(&self, other: &Self) -> bool {
+        !other.placed
+            && (other.top == Border::Any || self.top == other.top)
+            && (other.right == Border::Any || self.right == other.right)
+            && (other.bottom == Border::Any || self.bottom == other.bottom)
+            && (other.left == Border::Any || self.left == other.left)
+    }
+}
+
+impl From<&Tile> for u32 {
+    fn from(value: &Tile) -> Self {
+        (value.top as u32)
+            + (value.right as u32) * (1 << 4)
+            + (value.bottom as u32) * (1 << 8)
+            + (value.left as u32) * (1 << 16)
+            + (value.bridge as u32) * (1 << 20)
+    }
+}
+
+impl From<Tile> for u32 {
+    fn from(value: Tile) -> Self {
+        (value.top as u32)
+            + (value.right as u32) * (1 << 4)
+            + (value.bottom as u32) * (1 << 8)
+            + (value.left as u32) * (1 << 16)
+            + (value.bridge as u32) * (1 << 20)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotate90_turns_clockwise() {
+        assert_eq!(Tile::create("tr").rotate90(), Tile::create("rb"));
+        assert_eq!(Tile::create("trb").rotate90(), Tile::create("rbl"));
+        assert_eq!(Tile::create("tb=").rotate90(), Tile::create("rl="));
+    }
+
+    #[test]
+    fn rotations_compose() {
+        let tile = Tile::create("trl");
+        assert_eq!(tile.rotate90().rotate90(), tile.rotate180());
+        assert_eq!(tile.rotate90().rotate180(), tile.rotate270());
+        assert_eq!(tile.rotate90().rotate270(), tile);
+    }
+
+    #[test]
+    fn pattern_round_trips() {
+        for pattern in ["", "t", "tr", "tb", "trb", "trbl", "tb=", "trbl="] {
+            assert_eq!(Tile::create(pattern).pattern(), pattern);
+        }
+    }
+
+    #[test]
+    fn bridges_lead_straight_across() {
+        let bridge = Tile::create("trbl=");
+        let exits: Vec<Side> = bridge.exits(Some(Side::Left)).collect();
+        assert_eq!(exits, [Side::Right]);
+        let exits: Vec<Side> = bridge.exits(Some(Side::Top)).collect();
+        assert_eq!(exits, [Side::Bottom]);
+        assert_eq!(bridge.exits(None).count(), 4);
+        let crossing: Vec<Side> = Tile::create("trbl").exits(Some(Side::Left)).collect();
+        assert_eq!(crossing, Side::ALL);
+    }
+
+    #[test]
+    fn encoding_tells_tiles_apart() {
+        let tiles = [
+            Tile::create("tr"),
+            Tile::create("rb"),
+            Tile::create("bl"),
+            Tile::create("tl"),
+            Tile::create("trbl"),
+            Tile::create("trbl="),
+            Tile::default(),
+        ];
+        for (i, a) in tiles.iter().enumerate() {
+            assert_eq!(u32::from(a), u32::from(a.clone()));
+            for b in &tiles[i + 1..] {
+                assert_ne!(u32::from(a), u32::from(b), "{a:?} and {b:?}");
+            }
+        }
+    }
+}
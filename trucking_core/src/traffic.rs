@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+
+use crate::board::Board;
+use crate::level::Goods;
+use crate::score::TRUCK_SPEED;
+
+/// Seconds after a parcel arrives until the house orders the next one
+pub const ORDER_INTERVAL: f32 = 1.;
+/// How many trucks fit on a road tile unless the level sets a `capacity`
+pub const DEFAULT_CAPACITY: usize = 2;
+
+/// A depot or a house, with the goods it has or takes
+pub type Stop = ((i32, i32), Option<Goods>);
+
+/// A truck taking a parcel from a depot to a house
+#[derive(Debug, Clone, PartialEq)]
+pub struct Truck {
+    pub id: usize,
+    pub route: Vec<(i32, i32)>,
+    pub goods: Option<Goods>,
+    /// Tiles driven so far
+    pub driven: f32,
+}
+
+impl Truck {
+    pub fn length(&self) -> f32 {
+        (self.route.len() - 1) as f32
+    }
+
+    fn index(&self) -> usize {
+        ((self.driven + 0.5) as usize).min(self.route.len() - 1)
+    }
+
+    /// The road tile the truck is on, trucks do not jam the depot or the house
+    pub fn on_road(&self) -> Option<(i32, i32)> {
+        let i = self.index();
+        (i > 0 && i + 1 < self.route.len()).then(|| self.route[i])
+    }
+
+    /// The road tiles the truck is on or has yet to drive on
+    fn ahead(&self) -> &[(i32, i32)] {
+        &self.route[self.index().max(1)..self.route.len() - 1]
+    }
+}
+
+/// Connected houses that keep ordering parcels, and the trucks sharing the
+/// roads to deliver them. A house waits for its parcel before ordering the
+/// next one. A road tile used by more trucks than its capacity slows them all
+/// down, so new trucks avoid the busy roads.
+#[derive(Debug, Default, Clone)]
+pub struct Traffic {
+    pub capacity: usize,
+    pub trucks: Vec<Truck>,
+    pub delivered: usize,
+    /// Seconds since the first house was connected
+    pub seconds: f32,
+    /// Seconds until each connected house without a parcel on the way orders
+    orders: HashMap<(i32, i32), f32>,
+    next_id: usize,
+}
+
+impl Traffic {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ..Default::default()
+        }
+    }
+
+    /// How many trucks are on or headed for each road tile
+    pub fn load(&self) -> HashMap<(i32, i32), usize> {
+        let mut load = HashMap::new();
+        for pos in self.trucks.iter().flat_map(|t| t.ahead()) {
+            *load.entry(*pos).or_default() += 1;
+        }
+        load
+    }
+
+    /// How busy a road tile is, above one the traffic on it slows down
+    pub fn congestion(&self, load: usize) -> f32 {
+        load as f32 / self.capacity as f32
+    }
+
+    /// Drive the trucks for `seconds` and send new ones to the `houses` that
+    /// order, returns the trucks that arrived
+    pub fn step(
+        &mut self,
+        board: &Board,
+        depots: &[Stop],
+        houses: &[Stop],
+        seconds: f32,
+    ) -> Vec<Truck> {
+        if houses.is_empty() && self.seconds == 0. {
+            return vec![];
+        }
+        self.seconds += seconds;
+        // A truck on a road that was taken back has nowhere to go
+        self.trucks.retain(|t| {
+            t.route
+                .iter()
+                .all(|(x, y)| board.get(*x, *y).is_some_and(|t| t.placed))
+        });
+        let slowdown: HashMap<_, f32> = self
+            .load()
+            .into_iter()
+            .map(|(pos, load)| (pos, self.congestion(load).max(1.)))
+            .collect();
+        for truck in self.trucks.iter_mut() {
+            let slowdown = truck
+                .on_road()
+                .and_then(|p| slowdown.get(&p))
+                .unwrap_or(&1.);
+            truck.driven += TRUCK_SPEED * seconds / slowdown;
+        }
+        let (arrived, driving) = self.trucks.drain(..).partition(|t| t.driven >= t.length());
+        self.trucks = driving;
+        self.delivered += arrived.len();
+        for truck in arrived.iter() {
+            if let Some(house) = truck.route.last() {
+                self.orders.insert(*house, ORDER_INTERVAL);
+            }
+        }
+
+        self.orders
+            .retain(|pos, _| houses.iter().any(|(h, _)| h == pos));
+        let mut load = self.load();
+        for (house, goods) in houses {
+            if self.trucks.iter().any(|t| t.route.last() == Some(house)) {
+                continue;
+            }
+            let wait = self.orders.entry(*house).or_insert(0.);
+            *wait -= seconds;
+            if *wait > 0. {
+                continue;
+            }
+            let capacity = self.capacity;
+            // Entering a tile takes longer the more trucks use it
+            let cost = |pos| capacity.max(load.get(&pos).copied().unwrap_or(0) + 1);
+            let cheapest = depots
+                .iter()
+                .filter(|(_, g)| goods.is_none() || g == goods)
+                .filter_map(|(depot, g)| {
+                    board
+                        .cheapest_route(*depot, *house, cost)
+                        .map(|(c, route)| (c, route, *g))
+                })
+                .min_by_key(|(c, _, _)| *c);
+            let Some((_, route, goods)) = cheapest else {
+                *wait = 0.;
+                continue;
+            };
+            let truck = Truck {
+                id: self.next_id,
+                route,
+                goods,
+                driven: 0.,
+            };
+            for pos in truck.ahead() {
+                *load.entry(*pos).or_default() += 1;
+            }
+            self.next_id += 1;
+            self.trucks.push(truck);
+        }
+        arrived
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::Tile;
+
+    /// A depot at (0, 0) and a road to three houses around (4, 0)
+    fn fork() -> (Board, Vec<Stop>, Vec<Stop>) {
+        let mut board = Board::default();
+        let tiles = [
+            ((0, 0), "r"),
+            ((1, 0), "rl"),
+            ((2, 0), "rl"),
+            ((3, 0), "rl"),
+            ((4, 0), "trbl"),
+            ((4, 1), "b"),
+            ((4, -1), "t"),
+            ((5, 0), "l"),
+        ];
+        for ((x, y), _) in tiles {
+            board.add_cell(x, y);
+        }
+        board.apply_borders();
+        for ((x, y), tile) in tiles {
+            board.place(x, y, Tile::create(tile), true).unwrap();
+        }
+        let houses = [(4, 1), (4, -1), (5, 0)].map(|h| (h, None)).to_vec();
+        (board, vec![((0, 0), None)], houses)
+    }
+
+    fn run(capacity: usize, seconds: f32) -> Traffic {
+        let (board, depots, houses) = fork();
+        let mut traffic = Traffic::new(capacity);
+        for _ in 0..(seconds * 10.) as usize {
+            traffic.step(&board, &depots, &houses, 0.1);
+        }
+        traffic
+    }
+
+    #[test]
+    fn houses_order_one_parcel_at_a_time() {
+        let (board, depots, houses) = fork();
+        let mut traffic = Traffic::new(DEFAULT_CAPACITY);
+        assert!(traffic.step(&board, &depots, &[], 1.).is_empty());
+        assert_eq!(traffic.seconds, 0.);
+        traffic.step(&board, &depots, &houses, 0.1);
+        assert_eq!(traffic.trucks.len(), 3);
+        traffic.step(&board, &depots, &houses, 0.1);
+        assert_eq!(traffic.trucks.len(), 3);
+        assert_eq!(traffic.load()[&(2, 0)], 3);
+    }
+
+    #[test]
+    fn busy_roads_jam() {
+        let jammed = run(1, 20.);
+        let free = run(3, 20.);
+        assert!(jammed.delivered > 0);
+        assert!(jammed.delivered < free.delivered);
+        assert!(jammed.congestion(3) > free.congestion(3));
+    }
+}